@@ -0,0 +1,541 @@
+// admin_api.rs
+use rocket::http::Status;
+use rocket::response::status;
+use rocket::serde::json::{Json, Value as JsonValue};
+use rocket::{get, post, routes, Route, State};
+use serde::Serialize;
+use utoipa::{OpenApi, ToSchema};
+
+use crate::redis_registry::{AsyncRegistry, RegistryDescription};
+use crate::redis_registry_api::{failure_response, path_to_parts, validate_parts, ErrorResponse, OwnerRegistry};
+use crate::transform::{Transform, TransformFailure, TransformReport, TransformSample};
+use crate::read_cache::CacheStats;
+use crate::settings::{self, ConfigReloader, ReloadReport};
+use crate::auth::{ApiKey, TokenScopes};
+use crate::redact::load_redacted_prefixes;
+use crate::maintenance::{Maintenance, MaintenanceStatus, DEFAULT_RETRY_AFTER_SECS};
+use crate::slow_requests::{SlowRequest, SlowRequests};
+use crate::startup_gate::StartupGate;
+use crate::error_detail::ErrorVerbosity;
+
+// =======================================================
+// OpenAPI Documentation
+// =======================================================
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        resync_mirror_handler,
+        reload_handler,
+        transform_handler,
+        flush_namespace_handler,
+        quota_handler,
+        maintenance_handler,
+        schema_handler,
+        cache_warm_handler,
+        cache_stats_handler,
+        slow_requests_handler,
+        config_handler
+    ),
+    components(
+        schemas(ReloadReport, Transform, TransformReport, TransformFailure, TransformSample, QuotaUsage, MaintenanceStatus, SlowRequest, EffectiveConfig, RegistryDescription, CacheStatsResponse, CacheStats)
+    ),
+    tags(
+        (name = "admin", description = "Administration API")
+    )
+)]
+pub struct AdminApiDoc;
+
+// =======================================================
+// Response Types
+// =======================================================
+
+/// Key usage of an owner
+#[derive(Debug, Serialize, ToSchema)]
+pub struct QuotaUsage {
+    /// Number of keys, as tracked by the counter
+    pub used: u64,
+    /// Maximum number of keys (MAX_KEYS_PER_OWNER), absent when the quota is disabled
+    pub max_keys: Option<u64>,
+}
+
+/// Counters of the in-process caches of this server instance
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CacheStatsResponse {
+    /// Read cache counters, absent when the cache is disabled (CACHE_SIZE)
+    pub cache: Option<CacheStats>,
+    /// Stale cache counters, absent when stale reads are disabled (STALE_CACHE_SIZE)
+    pub stale_cache: Option<CacheStats>,
+    /// Change events of other instances applied to the caches (CACHE_PUBSUB)
+    pub remote_changes: u64,
+}
+
+/// Non-secret effective configuration of the server instance
+#[derive(Debug, Serialize, ToSchema)]
+pub struct EffectiveConfig {
+    pub registry: RegistryDescription,
+    /// Whether requests must carry a token (AUTH_TOKEN is set)
+    pub auth_enabled: bool,
+    /// Whether root operations are allowed without confirm_root (ALLOW_ROOT_SCAN)
+    pub allow_root_scan: bool,
+    /// Whether plain sets refuse to overwrite existing keys (STRICT_SET)
+    pub strict_set: bool,
+    /// Detail of server error responses (full or generic)
+    pub error_verbosity: String,
+    /// Whether Redis has been reached since startup (always true without STARTUP_GATE)
+    pub ready: bool,
+    /// Whether maintenance mode is enabled
+    pub maintenance: bool,
+}
+
+// =======================================================
+// REST API Handlers
+// =======================================================
+
+/// Copy all registry data from the primary to the mirror Redis
+#[utoipa::path(
+    post,
+    path = "/admin/mirror/resync",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Number of keys copied to the mirror", body = String),
+        (status = 400, description = "No mirror is configured", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Only the admin token may resync the mirror", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+#[post("/mirror/resync")]
+pub async fn resync_mirror_handler(api_key: ApiKey, registry: &State<AsyncRegistry>)
+                                   -> Result<status::Custom<String>, status::Custom<Json<ErrorResponse>>> {
+    debug!("Mirror resync request received");
+    let span = info_span!("resync_mirror_handler", identity = %api_key.identity());
+    let _guard = span.enter();
+
+    if !api_key.is_admin() {
+        warn!("Mirror resync rejected for a scoped token");
+        return Err(status::Custom(Status::Forbidden, Json(ErrorResponse::new("Only the admin token may resync the mirror".to_string()))));
+    }
+
+    match registry.resync_mirror().await {
+        Ok(Some(count)) => {
+            info!("Mirror resynced with {} keys", count);
+            Ok(status::Custom(Status::Ok, count.to_string()))
+        },
+        Ok(None) => {
+            warn!("Mirror resync requested but no mirror is configured");
+            Err(status::Custom(Status::BadRequest, Json(ErrorResponse::new("No mirror is configured".to_string()))))
+        },
+        Err(e) => Err(failure_response(&e, format_args!("Failed to resync mirror"))),
+    }
+}
+
+/// Reload the configuration without restarting the server
+#[utoipa::path(
+    post,
+    path = "/admin/reload",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Settings applied and settings that require a restart", body = ReloadReport),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Only the admin token may reload the configuration", body = ErrorResponse)
+    )
+)]
+#[post("/reload")]
+pub async fn reload_handler(api_key: ApiKey, registry: &State<AsyncRegistry>, reloader: &State<ConfigReloader>, scopes: &State<TokenScopes>)
+                            -> Result<Json<ReloadReport>, status::Custom<Json<ErrorResponse>>> {
+    debug!("Configuration reload request received");
+    let span = info_span!("reload_handler", identity = %api_key.identity());
+    let _guard = span.enter();
+
+    if !api_key.is_admin() {
+        warn!("Configuration reload rejected for a scoped token");
+        return Err(status::Custom(Status::Forbidden, Json(ErrorResponse::new("Only the admin token may reload the configuration".to_string()))));
+    }
+
+    let report = reloader.reload();
+    registry.reload_settings();
+    scopes.reload();
+    load_redacted_prefixes();
+
+    info!("Configuration reloaded: applied={:?}, requires_restart={:?}", report.applied, report.requires_restart);
+    Ok(Json(report))
+}
+
+/// Apply a registered transform to every value under a prefix (e.g. to add a field during a migration)
+#[utoipa::path(
+    post,
+    path = "/admin/transform",
+    tag = "admin",
+    params(
+        ("path" = Option<String>, Query, description = "Key path prefix as a string (can be empty or nested using forward slashes like 'a/b/c')"),
+        ("dry_run" = Option<bool>, Query, description = "Only report the counts and a sample of before/after values without writing (default: false)")
+    ),
+    request_body = Transform,
+    responses(
+        (status = 200, description = "Number of transformed values and failures", body = TransformReport),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Only the admin token may transform values", body = ErrorResponse),
+        (status = 409, description = "Another bulk operation on an overlapping prefix is in progress", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+#[post("/transform?<path>&<dry_run>", format = "json", data = "<transform>")]
+pub async fn transform_handler(api_key: ApiKey, registry: OwnerRegistry, path: Option<String>, dry_run: Option<bool>, transform: Json<Transform>)
+                               -> Result<Json<TransformReport>, status::Custom<Json<ErrorResponse>>> {
+    debug!("Transform request received for path prefix: {:?}, dry_run: {:?}", path, dry_run);
+    let span = info_span!("transform_handler", identity = %api_key.identity(), path = ?path, dry_run = ?dry_run);
+    let _guard = span.enter();
+
+    if !api_key.is_admin() {
+        warn!("Transform rejected for a scoped token");
+        return Err(status::Custom(Status::Forbidden, Json(ErrorResponse::new("Only the admin token may transform values".to_string()))));
+    }
+
+    let parts = path_to_parts(&path);
+    validate_parts(&parts)?;
+
+    match registry.transform(&parts, &transform, dry_run.unwrap_or(false)).await {
+        Ok(report) => {
+            info!("Transformed {} values with prefix: {:?} ({} failures)", report.transformed, path, report.failures.len());
+            Ok(Json(report))
+        },
+        Err(e) => Err(failure_response(&e, format_args!("Failed to transform values with prefix {:?}", path))),
+    }
+}
+
+/// Delete every key of a namespace (owner type) across all owners
+#[utoipa::path(
+    post,
+    path = "/admin/flush-namespace",
+    tag = "admin",
+    params(
+        ("namespace" = String, Query, description = "Namespace (owner type) to wipe"),
+        ("confirm" = String, Query, description = "Must repeat the namespace to confirm the operation")
+    ),
+    responses(
+        (status = 200, description = "Number of deleted keys", body = String),
+        (status = 400, description = "Missing or mismatching confirmation, or invalid namespace", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Only the admin token may flush a namespace", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+#[post("/flush-namespace?<namespace>&<confirm>")]
+pub async fn flush_namespace_handler(api_key: ApiKey, registry: &State<AsyncRegistry>, namespace: String, confirm: Option<String>)
+                                     -> Result<status::Custom<String>, status::Custom<Json<ErrorResponse>>> {
+    debug!("Flush namespace request received for namespace: {}", namespace);
+    let span = info_span!("flush_namespace_handler", identity = %api_key.identity(), namespace = %namespace);
+    let _guard = span.enter();
+
+    if !api_key.is_admin() {
+        warn!("Flush namespace rejected for a scoped token");
+        return Err(status::Custom(Status::Forbidden, Json(ErrorResponse::new("Only the admin token may flush a namespace".to_string()))));
+    }
+
+    if confirm.as_deref() != Some(namespace.as_str()) {
+        warn!("Flush of namespace {} rejected: confirmation does not match", namespace);
+        return Err(status::Custom(Status::BadRequest, Json(ErrorResponse::new("The confirm parameter must repeat the namespace".to_string()))));
+    }
+
+    match registry.flush_namespace(&namespace).await {
+        Ok(count) => {
+            info!("Flushed {} keys of namespace: {}", count, namespace);
+            Ok(status::Custom(Status::Ok, count.to_string()))
+        },
+        Err(e) => Err(failure_response(&e, format_args!("Failed to flush namespace {}", namespace))),
+    }
+}
+
+/// Report the owner's key usage, optionally reconciling the counter with a scan first
+#[utoipa::path(
+    get,
+    path = "/admin/quota",
+    tag = "admin",
+    params(
+        ("reconcile" = Option<bool>, Query, description = "Recount the owner's keys with a scan to fix counter drift (default: false)")
+    ),
+    responses(
+        (status = 200, description = "Key usage of the owner", body = QuotaUsage),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Only the admin token may reconcile the key counter", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+#[get("/quota?<reconcile>")]
+pub async fn quota_handler(api_key: ApiKey, registry: OwnerRegistry, reconcile: Option<bool>)
+                           -> Result<Json<QuotaUsage>, status::Custom<Json<ErrorResponse>>> {
+    debug!("Quota request received, reconcile: {:?}", reconcile);
+    let span = info_span!("quota_handler", identity = %api_key.identity(), reconcile = ?reconcile);
+    let _guard = span.enter();
+
+    if reconcile.unwrap_or(false) && !api_key.is_admin() {
+        warn!("Quota reconcile rejected for a scoped token");
+        return Err(status::Custom(Status::Forbidden, Json(ErrorResponse::new("Only the admin token may reconcile the key counter".to_string()))));
+    }
+
+    let result = if reconcile.unwrap_or(false) {
+        registry.reconcile_key_usage().await
+    } else {
+        registry.key_usage().await
+    };
+
+    match result {
+        Ok(used) => {
+            info!("Key usage: {} of {:?}", used, registry.max_keys());
+            Ok(Json(QuotaUsage { used, max_keys: registry.max_keys() }))
+        },
+        Err(e) => Err(failure_response(&e, format_args!("Failed to get key usage"))),
+    }
+}
+
+/// Enable or disable maintenance mode, rejecting all /registry/* requests with 503 while it is enabled
+#[utoipa::path(
+    post,
+    path = "/admin/maintenance",
+    tag = "admin",
+    params(
+        ("enabled" = bool, Query, description = "Whether registry routes are rejected with 503 Service Unavailable"),
+        ("retry_after" = Option<u64>, Query, description = "Delay in seconds suggested to clients in the Retry-After header (default: 60)")
+    ),
+    responses(
+        (status = 200, description = "Maintenance mode now in effect", body = MaintenanceStatus),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Only the admin token may toggle maintenance mode", body = ErrorResponse)
+    )
+)]
+#[post("/maintenance?<enabled>&<retry_after>")]
+pub async fn maintenance_handler(api_key: ApiKey, maintenance: &State<Maintenance>, enabled: bool, retry_after: Option<u64>)
+                                 -> Result<Json<MaintenanceStatus>, status::Custom<Json<ErrorResponse>>> {
+    debug!("Maintenance request received, enabled: {}, retry after: {:?}", enabled, retry_after);
+    let span = info_span!("maintenance_handler", identity = %api_key.identity(), enabled = enabled);
+    let _guard = span.enter();
+
+    if !api_key.is_admin() {
+        warn!("Maintenance toggle rejected for a scoped token");
+        return Err(status::Custom(Status::Forbidden, Json(ErrorResponse::new("Only the admin token may toggle maintenance mode".to_string()))));
+    }
+
+    maintenance.set(enabled, retry_after.unwrap_or(DEFAULT_RETRY_AFTER_SECS));
+    let status = maintenance.status();
+    if status.enabled {
+        warn!("Maintenance mode enabled, registry requests are rejected (Retry-After: {}s)", status.retry_after);
+    } else {
+        info!("Maintenance mode disabled");
+    }
+    Ok(Json(status))
+}
+
+/// List the slowest requests served since startup (or since the last reset), slowest first
+#[utoipa::path(
+    get,
+    path = "/admin/slow-requests",
+    tag = "admin",
+    params(
+        ("reset" = Option<bool>, Query, description = "Forget the recorded requests after returning them (default: false)")
+    ),
+    responses(
+        (status = 200, description = "Slowest requests, at most SLOW_REQUESTS_SIZE", body = [SlowRequest]),
+        (status = 400, description = "Slow request recording is disabled", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Only the admin token may list slow requests", body = ErrorResponse)
+    )
+)]
+#[get("/slow-requests?<reset>")]
+pub async fn slow_requests_handler(api_key: ApiKey, slow_requests: &State<SlowRequests>, reset: Option<bool>)
+                                   -> Result<Json<Vec<SlowRequest>>, status::Custom<Json<ErrorResponse>>> {
+    debug!("Slow requests request received, reset: {:?}", reset);
+    let span = info_span!("slow_requests_handler", identity = %api_key.identity());
+    let _guard = span.enter();
+
+    if !api_key.is_admin() {
+        warn!("Slow requests listing rejected for a scoped token");
+        return Err(status::Custom(Status::Forbidden, Json(ErrorResponse::new("Only the admin token may list slow requests".to_string()))));
+    }
+    if !slow_requests.is_enabled() {
+        warn!("Slow requests listing rejected: recording is disabled");
+        return Err(status::Custom(Status::BadRequest, Json(ErrorResponse::new("Slow request recording is disabled".to_string()))));
+    }
+
+    let requests = slow_requests.snapshot();
+    if reset.unwrap_or(false) {
+        slow_requests.clear();
+        info!("Slow requests reset");
+    }
+    Ok(Json(requests))
+}
+
+/// Report the owner and the non-secret configuration this instance is serving, without touching any data
+#[utoipa::path(
+    get,
+    path = "/admin/config",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Effective configuration; Redis targets are reported without credentials", body = EffectiveConfig),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Only the admin token may read the configuration", body = ErrorResponse)
+    )
+)]
+#[get("/config")]
+pub async fn config_handler(api_key: ApiKey, registry: &State<AsyncRegistry>, gate: &State<StartupGate>, maintenance: &State<Maintenance>)
+                            -> Result<Json<EffectiveConfig>, status::Custom<Json<ErrorResponse>>> {
+    debug!("Config request received");
+    let span = info_span!("config_handler", identity = %api_key.identity());
+    let _guard = span.enter();
+
+    if !api_key.is_admin() {
+        warn!("Config request rejected for a scoped token");
+        return Err(status::Custom(Status::Forbidden, Json(ErrorResponse::new("Only the admin token may read the configuration".to_string()))));
+    }
+
+    let enabled = |name: &str| settings::var(name).map(|value| value.eq_ignore_ascii_case("true") || value == "1").unwrap_or(false);
+    let config = EffectiveConfig {
+        registry: registry.describe(),
+        auth_enabled: settings::var("AUTH_TOKEN").is_ok_and(|token| token != "disabled"),
+        allow_root_scan: enabled("ALLOW_ROOT_SCAN"),
+        strict_set: enabled("STRICT_SET"),
+        error_verbosity: format!("{:?}", ErrorVerbosity::from_env()).to_lowercase(),
+        ready: gate.is_ready(),
+        maintenance: maintenance.status().enabled,
+    };
+    info!("Reporting configuration of owner {}", config.registry.owner_prefix);
+    Ok(Json(config))
+}
+
+/// Register the JSON Schema of the values below a prefix, returned by /registry/get-with-schema
+#[utoipa::path(
+    post,
+    path = "/admin/schema",
+    tag = "admin",
+    params(
+        ("path" = Option<String>, Query, description = "Key path prefix as a string (can be empty or nested using forward slashes like 'a/b/c')")
+    ),
+    request_body(content = JsonValue, description = "JSON Schema of the values below the prefix, or null to remove it"),
+    responses(
+        (status = 204, description = "Schema registered or removed"),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Only the admin token may register schemas", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+#[post("/schema?<path>", format = "json", data = "<schema>")]
+pub async fn schema_handler(api_key: ApiKey, registry: OwnerRegistry, path: Option<String>, schema: Json<JsonValue>)
+                            -> Result<Status, status::Custom<Json<ErrorResponse>>> {
+    debug!("Schema request received for path prefix: {:?}", path);
+    let span = info_span!("schema_handler", identity = %api_key.identity(), path = ?path);
+    let _guard = span.enter();
+
+    if !api_key.is_admin() {
+        warn!("Schema registration rejected for a scoped token");
+        return Err(status::Custom(Status::Forbidden, Json(ErrorResponse::new("Only the admin token may register schemas".to_string()))));
+    }
+
+    let parts = path_to_parts(&path);
+    validate_parts(&parts)?;
+    let schema = Some(schema.into_inner()).filter(|schema| !schema.is_null());
+
+    match registry.set_schema(&parts, schema.as_ref()).await {
+        Ok(()) => {
+            info!("Schema for prefix {:?} {}", path, if schema.is_some() { "registered" } else { "removed" });
+            Ok(Status::NoContent)
+        },
+        Err(e) => Err(failure_response(&e, format_args!("Failed to store schema for prefix {:?}", path))),
+    }
+}
+
+/// Load the values below a prefix into the in-process read cache, e.g. right after a deploy
+#[utoipa::path(
+    post,
+    path = "/admin/cache-warm",
+    tag = "admin",
+    params(
+        ("path" = Option<String>, Query, description = "Key path prefix as a string (can be empty or nested using forward slashes like 'a/b/c')")
+    ),
+    responses(
+        (status = 200, description = "Number of values loaded into the read cache", body = String),
+        (status = 400, description = "The read cache is disabled", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Only the admin token may warm the read cache", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+#[post("/cache-warm?<path>")]
+pub async fn cache_warm_handler(api_key: ApiKey, registry: &State<AsyncRegistry>, path: Option<String>)
+                                -> Result<status::Custom<String>, status::Custom<Json<ErrorResponse>>> {
+    debug!("Cache warm request received for path prefix: {:?}", path);
+    let span = info_span!("cache_warm_handler", identity = %api_key.identity(), path = ?path);
+    let _guard = span.enter();
+
+    if !api_key.is_admin() {
+        warn!("Cache warm rejected for a scoped token");
+        return Err(status::Custom(Status::Forbidden, Json(ErrorResponse::new("Only the admin token may warm the read cache".to_string()))));
+    }
+
+    let parts = path_to_parts(&path);
+    validate_parts(&parts)?;
+
+    match registry.warm_cache(&parts).await {
+        Ok(Some(count)) => {
+            info!("Warmed read cache with {} values with prefix: {:?}", count, path);
+            Ok(status::Custom(Status::Ok, count.to_string()))
+        },
+        Ok(None) => {
+            warn!("Cache warm requested but the read cache is disabled");
+            Err(status::Custom(Status::BadRequest, Json(ErrorResponse::new("The read cache is disabled (set CACHE_SIZE)".to_string()))))
+        },
+        Err(e) => Err(failure_response(&e, format_args!("Failed to warm read cache with prefix {:?}", path))),
+    }
+}
+
+/// Report the hits, misses and invalidations of the in-process caches since startup
+#[utoipa::path(
+    get,
+    path = "/admin/cache-stats",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Counters of the read cache, the stale cache and the applied change events of other instances", body = CacheStatsResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Only the admin token may read the cache statistics", body = ErrorResponse)
+    )
+)]
+#[get("/cache-stats")]
+pub async fn cache_stats_handler(api_key: ApiKey, registry: &State<AsyncRegistry>)
+                                 -> Result<Json<CacheStatsResponse>, status::Custom<Json<ErrorResponse>>> {
+    debug!("Cache stats request received");
+    let span = info_span!("cache_stats_handler", identity = %api_key.identity());
+    let _guard = span.enter();
+
+    if !api_key.is_admin() {
+        warn!("Cache stats request rejected for a scoped token");
+        return Err(status::Custom(Status::Forbidden, Json(ErrorResponse::new("Only the admin token may read the cache statistics".to_string()))));
+    }
+
+    let (cache, stale_cache, remote_changes) = registry.cache_stats();
+    Ok(Json(CacheStatsResponse { cache, stale_cache, remote_changes }))
+}
+
+// =======================================================
+// Route Definitions
+// =======================================================
+
+// Function to get admin routes
+pub fn routes() -> Vec<Route> {
+    routes![
+        resync_mirror_handler,
+        reload_handler,
+        transform_handler,
+        flush_namespace_handler,
+        quota_handler,
+        maintenance_handler,
+        schema_handler,
+        cache_warm_handler,
+        cache_stats_handler,
+        slow_requests_handler,
+        config_handler
+    ]
+}
+
+// Function to mount admin routes
+pub fn mount_admin_routes(rocket: rocket::Rocket<rocket::Build>) -> rocket::Rocket<rocket::Build> {
+    rocket.mount("/admin", routes())
+}
@@ -2,15 +2,147 @@
 use rocket::http::Status;
 use rocket::request::{FromRequest, Outcome};
 use rocket::Request;
-use std::env;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::RwLock;
 
+use crate::redis_registry_api::path_to_parts;
+use crate::settings;
+
+/// Authenticated token, with its scope when it is a scoped token
 #[allow(dead_code)]
-pub struct ApiKey(pub String);
+pub struct ApiKey(pub String, pub Option<TokenScope>);
+
+impl ApiKey {
+    /// Label identifying the caller in logs, never the raw token
+    /// The admin token is labelled by AUTH_TOKEN_LABEL (default "admin"), scoped tokens by their `label`
+    pub fn identity(&self) -> String {
+        match &self.1 {
+            Some(scope) => scope.label.clone().unwrap_or_else(|| "scoped".to_string()),
+            None if self.0 == "disabled" => "anonymous".to_string(),
+            None => settings::var("AUTH_TOKEN_LABEL").unwrap_or_else(|_| "admin".to_string()),
+        }
+    }
+
+    /// Check whether this is the unrestricted admin token (or authentication is disabled)
+    pub fn is_admin(&self) -> bool {
+        self.1.is_none()
+    }
+
+    /// Check whether the token may use the operation on the specified key parts
+    pub fn permits(&self, operation: &str, parts: &[String]) -> bool {
+        match &self.1 {
+            Some(scope) => scope.permits(operation) && scope.permits_path(parts),
+            None => true,
+        }
+    }
+}
 
 #[derive(Debug)]
 pub enum ApiKeyError {
     Missing,
     Invalid,
+    Forbidden,
+}
+
+/// Operations and key prefix a scoped token may use
+/// Operation names are the handler names without the `_handler` suffix (e.g. get, set, scan, purge)
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct TokenScope {
+    /// If present, only these operations are allowed
+    #[serde(default)]
+    pub allow: Option<Vec<String>>,
+    /// Operations that are always rejected
+    #[serde(default)]
+    pub deny: Vec<String>,
+    /// If present, only key paths at or below this prefix are accessible (e.g. "teamA")
+    #[serde(default)]
+    pub prefix: Option<String>,
+    /// Caller identity recorded in the logs instead of the token (default: "scoped")
+    #[serde(default)]
+    pub label: Option<String>,
+}
+
+impl TokenScope {
+    /// Check whether the scope allows the specified operation
+    pub fn permits(&self, operation: &str) -> bool {
+        if self.deny.iter().any(|op| op == operation) {
+            return false;
+        }
+        match &self.allow {
+            Some(allowed) => allowed.iter().any(|op| op == operation),
+            None => true,
+        }
+    }
+
+    /// Check whether the scope allows access to the specified key parts
+    pub fn permits_path(&self, parts: &[String]) -> bool {
+        match &self.prefix {
+            Some(prefix) => parts.starts_with(&path_to_parts(&Some(prefix.clone()))),
+            None => true,
+        }
+    }
+}
+
+/// Additional tokens with restricted scopes, loaded from TOKEN_SCOPES_FILE or TOKEN_SCOPES
+/// The AUTH_TOKEN itself is never restricted
+pub struct TokenScopes {
+    scopes: RwLock<HashMap<String, TokenScope>>,
+}
+
+/// Read the token scope mapping ({"<token>": {"allow": [...], "deny": [...], "prefix": "...", "label": "..."}}) from the environment
+fn load_token_scopes() -> HashMap<String, TokenScope> {
+    let (source, json) = match (settings::var("TOKEN_SCOPES_FILE"), settings::var("TOKEN_SCOPES")) {
+        (Ok(path), _) => match std::fs::read_to_string(&path) {
+            Ok(json) => (path, json),
+            Err(e) => {
+                error!("Failed to read TOKEN_SCOPES_FILE {}: {}", path, e);
+                return HashMap::new();
+            }
+        },
+        (_, Ok(json)) => ("TOKEN_SCOPES".to_string(), json),
+        _ => return HashMap::new(),
+    };
+
+    match serde_json::from_str::<HashMap<String, TokenScope>>(&json) {
+        Ok(scopes) => {
+            info!("Loaded {} scoped tokens from {}", scopes.len(), source);
+            scopes
+        }
+        Err(e) => {
+            error!("Failed to parse token scopes from {}: {}", source, e);
+            HashMap::new()
+        }
+    }
+}
+
+impl TokenScopes {
+    pub fn from_env() -> Self {
+        TokenScopes::new(load_token_scopes())
+    }
+
+    pub fn new(scopes: HashMap<String, TokenScope>) -> Self {
+        TokenScopes {
+            scopes: RwLock::new(scopes),
+        }
+    }
+
+    /// Re-read the token scope mapping from the environment
+    pub fn reload(&self) {
+        *self.scopes.write().unwrap() = load_token_scopes();
+    }
+
+    fn get(&self, token: &str) -> Option<TokenScope> {
+        self.scopes.read().unwrap().get(token).cloned()
+    }
+}
+
+/// Name of the operation served by the matched route (the handler name without `_handler`)
+fn request_operation<'r>(request: &'r Request<'_>) -> Option<&'r str> {
+    request
+        .route()
+        .and_then(|route| route.name.as_deref())
+        .map(|name| name.trim_end_matches("_handler"))
 }
 
 #[rocket::async_trait]
@@ -19,18 +151,18 @@ impl<'r> FromRequest<'r> for ApiKey {
 
     async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
         // Get the auth token from the environment
-        let auth_token = match env::var("AUTH_TOKEN") {
+        let auth_token = match settings::var("AUTH_TOKEN") {
             Ok(token) => token,
             Err(_) => {
                 // If the AUTH_TOKEN is not set, authentication is effectively disabled
                 warn!("AUTH_TOKEN environment variable not set. API requests will not be authenticated!");
-                return Outcome::Success(ApiKey("disabled".to_string()));
+                return Outcome::Success(ApiKey("disabled".to_string(), None));
             }
         };
 
         // If authentication is disabled, all requests are allowed
         if auth_token == "disabled" {
-            return Outcome::Success(ApiKey("disabled".to_string()));
+            return Outcome::Success(ApiKey("disabled".to_string(), None));
         }
 
         // Check if the Authorization header is present
@@ -47,12 +179,81 @@ impl<'r> FromRequest<'r> for ApiKey {
 
                 // Check if the token matches
                 if token == auth_token {
-                    return Outcome::Success(ApiKey(token.to_string()));
-                } else {
-                    return Outcome::Error((Status::Unauthorized, ApiKeyError::Invalid));
+                    return Outcome::Success(ApiKey(token.to_string(), None));
+                }
+
+                // Otherwise it may be a scoped token restricted to some operations and a key prefix
+                let scope = request
+                    .rocket()
+                    .state::<TokenScopes>()
+                    .and_then(|scopes| scopes.get(token));
+                match scope {
+                    Some(scope) => {
+                        let operation = request_operation(request).unwrap_or_default();
+                        let path = request.query_value::<String>("path").and_then(Result::ok);
+                        if !scope.permits(operation) {
+                            warn!("Scoped token {} is not allowed to use operation: {}", scope.label.as_deref().unwrap_or("scoped"), operation);
+                            Outcome::Error((Status::Forbidden, ApiKeyError::Forbidden))
+                        } else if !scope.permits_path(&path_to_parts(&path)) {
+                            warn!("Scoped token {} is not allowed to access path: {:?}", scope.label.as_deref().unwrap_or("scoped"), path);
+                            Outcome::Error((Status::Forbidden, ApiKeyError::Forbidden))
+                        } else {
+                            Outcome::Success(ApiKey(token.to_string(), Some(scope)))
+                        }
+                    }
+                    None => Outcome::Error((Status::Unauthorized, ApiKeyError::Invalid)),
                 }
             }
             None => Outcome::Error((Status::Unauthorized, ApiKeyError::Missing)),
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scope(allow: Option<&[&str]>, deny: &[&str], prefix: Option<&str>) -> TokenScope {
+        TokenScope {
+            allow: allow.map(|ops| ops.iter().map(|op| op.to_string()).collect()),
+            deny: deny.iter().map(|op| op.to_string()).collect(),
+            prefix: prefix.map(str::to_string),
+            label: None,
+        }
+    }
+
+    #[test]
+    fn deny_takes_precedence_over_allow() {
+        let unrestricted = scope(None, &[], None);
+        assert!(unrestricted.permits("purge"));
+
+        let scoped = scope(Some(&["get", "set"]), &["set"], None);
+        assert!(scoped.permits("get"));
+        assert!(!scoped.permits("set"));
+        assert!(!scoped.permits("scan"));
+
+        let denied = scope(None, &["purge"], None);
+        assert!(denied.permits("get"));
+        assert!(!denied.permits("purge"));
+    }
+
+    fn parts(path: &str) -> Vec<String> {
+        path_to_parts(&Some(path.to_string()))
+    }
+
+    #[test]
+    fn prefixes_match_whole_segments() {
+        let scoped = scope(None, &[], Some("teamA/"));
+        assert!(scoped.permits_path(&parts("teamA")));
+        assert!(scoped.permits_path(&parts("teamA/config")));
+        assert!(!scoped.permits_path(&parts("teamAB/config")));
+        assert!(!scoped.permits_path(&parts("teamB")));
+        assert!(!scoped.permits_path(&[]));
+
+        let key = ApiKey("token".to_string(), Some(scope(Some(&["get"]), &[], Some("teamA"))));
+        assert!(key.permits("get", &parts("teamA/x")));
+        assert!(!key.permits("set", &parts("teamA/x")));
+        assert!(!key.permits("get", &parts("teamB/x")));
+        assert!(ApiKey("admin".to_string(), None).permits("purge", &[]));
+    }
+}
@@ -0,0 +1,78 @@
+// cache_sync.rs
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::futures::StreamExt;
+use rocket::tokio::time::sleep;
+use rocket::{Orbit, Rocket};
+use std::time::Duration;
+
+use crate::redis_registry::AsyncRegistry;
+
+// =======================================================
+// Cache invalidation across instances
+// =======================================================
+// The read caches of an instance only see its own writes. With CACHE_PUBSUB=true every instance publishes
+// its writes on the owner's change channel and evicts the keys written by the other instances
+
+/// Delay before subscribing again after the pub/sub connection was lost or could not be opened
+const RESUBSCRIBE_DELAY_SECS: u64 = 1;
+
+/// Fairing subscribing to the change channel after launch and applying the events to the read caches
+pub struct CacheSyncFairing;
+
+#[rocket::async_trait]
+impl Fairing for CacheSyncFairing {
+    fn info(&self) -> Info {
+        Info {
+            name: "Cache invalidation",
+            kind: Kind::Liftoff,
+        }
+    }
+
+    async fn on_liftoff(&self, rocket: &Rocket<Orbit>) {
+        let Some(registry) = rocket.state::<AsyncRegistry>().cloned() else {
+            error!("AsyncRegistry is not managed by Rocket");
+            return;
+        };
+
+        let shutdown = rocket.shutdown();
+        rocket::tokio::spawn(async move {
+            let mut subscribed_before = false;
+            loop {
+                match registry.subscribe_remote_changes().await {
+                    Ok(None) => return,
+                    Ok(Some(mut pubsub)) => {
+                        // Events published while the connection was down are lost, so nothing cached may be trusted
+                        if subscribed_before {
+                            info!("Resubscribed to change events, clearing the read caches");
+                            registry.clear_caches();
+                        }
+                        subscribed_before = true;
+
+                        let mut messages = pubsub.on_message();
+                        loop {
+                            rocket::tokio::select! {
+                                message = messages.next() => match message {
+                                    Some(message) => match message.get_payload::<String>() {
+                                        Ok(payload) => registry.apply_remote_change(&payload),
+                                        Err(e) => warn!("Ignoring change event with an invalid payload: {}", e),
+                                    },
+                                    None => {
+                                        warn!("Lost the change event subscription");
+                                        break;
+                                    },
+                                },
+                                _ = shutdown.clone() => return,
+                            }
+                        }
+                    },
+                    Err(e) => warn!("Failed to subscribe to change events: {}", e),
+                }
+
+                rocket::tokio::select! {
+                    _ = sleep(Duration::from_secs(RESUBSCRIBE_DELAY_SECS)) => {},
+                    _ = shutdown.clone() => return,
+                }
+            }
+        });
+    }
+}
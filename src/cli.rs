@@ -0,0 +1,177 @@
+// cli.rs
+use clap::{Parser, Subcommand};
+use rocket::tokio::fs::File;
+use rocket::tokio::io::{self, AsyncWrite, AsyncWriteExt};
+use std::path::PathBuf;
+
+use crate::redis_registry::{AsyncRegistry, PIPELINE_BATCH_SIZE};
+use crate::redis_registry_api::path_to_parts;
+
+// =======================================================
+// Command line mode
+// =======================================================
+// Running the binary with a subcommand performs a single registry operation with the same configuration
+// as the server (.env and environment variables) and prints its result; without one the server starts
+
+#[derive(Debug, Parser)]
+#[command(name = "redis_registry", version, about = "Hierarchical key-value registry on top of Redis")]
+pub struct Cli {
+    /// Operation to run instead of starting the server
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Print the value of a key as JSON
+    Get {
+        /// Key path (e.g. a/b/c)
+        path: String,
+    },
+    /// Set a key to a JSON value
+    Set {
+        /// Key path (e.g. a/b/c)
+        path: String,
+        /// JSON value (e.g. '{"name": "John"}' or '"text"')
+        value: String,
+    },
+    /// Print the keys below a prefix, one per line
+    Scan {
+        /// Key path prefix (default: the whole owner keyspace)
+        #[arg(default_value = "")]
+        path: String,
+    },
+    /// Print the keys and values below a prefix as a JSON object
+    Dump {
+        /// Key path prefix (default: the whole owner keyspace)
+        #[arg(default_value = "")]
+        path: String,
+        /// Pretty-print the JSON
+        #[arg(long)]
+        pretty: bool,
+    },
+    /// Write the keys and values below a prefix as NDJSON lines, the format read by /registry/restore-stream
+    Backup {
+        /// Key path prefix (default: the whole owner keyspace)
+        #[arg(default_value = "")]
+        path: String,
+        /// File to write instead of standard output
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+}
+
+/// Run a command line operation, printing its result to standard output
+pub async fn run(command: Command, registry: &AsyncRegistry) -> Result<(), String> {
+    debug!("Running command: {:?}", command);
+    match command {
+        Command::Get { path } => {
+            let parts = path_to_parts(&Some(path.clone()));
+            match registry.get_with_cache_status(&parts).await.map_err(|e| e.to_string())? {
+                (Some(value), _) => print_json(&value, true),
+                (None, _) => Err(format!("Key not found: {}", path)),
+            }
+        }
+        Command::Set { path, value } => {
+            let parts = path_to_parts(&Some(path.clone()));
+            let value = serde_json::from_str(&value).map_err(|e| format!("Invalid JSON value: {}", e))?;
+            registry.set(&parts, value).await.map_err(|e| e.to_string())?;
+            info!("Set value for path: {}", path);
+            Ok(())
+        }
+        Command::Scan { path } => {
+            let keys = registry.scan(&path_to_parts(&Some(path))).await.map_err(|e| e.to_string())?;
+            for key in keys {
+                println!("{}", key);
+            }
+            Ok(())
+        }
+        Command::Dump { path, pretty } => {
+            let dump = registry.dump(&path_to_parts(&Some(path))).await.map_err(|e| e.to_string())?;
+            print_json(&dump, pretty)
+        }
+        Command::Backup { path, output } => {
+            let parts = path_to_parts(&Some(path));
+            let count = match &output {
+                Some(file) => {
+                    let file = File::create(file).await.map_err(|e| format!("Failed to create {}: {}", file.display(), e))?;
+                    write_backup(registry, &parts, file).await?
+                }
+                None => write_backup(registry, &parts, io::stdout()).await?,
+            };
+            info!("Backed up {} keys", count);
+            Ok(())
+        }
+    }
+}
+
+fn print_json(value: &serde_json::Value, pretty: bool) -> Result<(), String> {
+    let json = if pretty { serde_json::to_string_pretty(value) } else { serde_json::to_string(value) };
+    println!("{}", json.map_err(|e| e.to_string())?);
+    Ok(())
+}
+
+/// Read the prefix batch by batch and write it as `{"key": ..., "value": ...}` lines, returning the number of keys
+async fn write_backup(registry: &AsyncRegistry, parts: &[String], mut writer: impl AsyncWrite + Unpin) -> Result<usize, String> {
+    let keys = registry.scan(parts).await.map_err(|e| e.to_string())?;
+    let mut count = 0;
+    for chunk in keys.chunks(PIPELINE_BATCH_SIZE) {
+        let entries = registry.read_entries(parts, chunk.to_vec()).await.map_err(|e| e.to_string())?;
+        let mut data = Vec::new();
+        for (key, value) in entries {
+            let line = serde_json::to_string(&serde_json::json!({ "key": key, "value": value })).map_err(|e| e.to_string())?;
+            data.extend(line.into_bytes());
+            data.push(b'\n');
+            count += 1;
+        }
+        writer.write_all(&data).await.map_err(|e| format!("Failed to write backup: {}", e))?;
+    }
+    writer.flush().await.map_err(|e| format!("Failed to write backup: {}", e))?;
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::redis_registry::{RegistryConfig, StorageBackend};
+
+    #[test]
+    fn subcommands_parse_with_their_defaults() {
+        let cli = Cli::try_parse_from(["redis_registry", "get", "a/b"]).unwrap();
+        assert!(matches!(cli.command, Some(Command::Get { path }) if path == "a/b"));
+        let cli = Cli::try_parse_from(["redis_registry", "scan"]).unwrap();
+        assert!(matches!(cli.command, Some(Command::Scan { path }) if path.is_empty()));
+        let cli = Cli::try_parse_from(["redis_registry", "dump", "a", "--pretty"]).unwrap();
+        assert!(matches!(cli.command, Some(Command::Dump { path, pretty: true }) if path == "a"));
+        assert!(Cli::try_parse_from(["redis_registry"]).unwrap().command.is_none());
+        assert!(Cli::try_parse_from(["redis_registry", "set", "a/b"]).is_err());
+    }
+
+    #[rocket::async_test]
+    #[ignore = "requires a Redis server at REDIS_URL"]
+    async fn commands_read_and_write_the_registry() {
+        let registry = AsyncRegistry::new(&RegistryConfig {
+            owner_type: "test".to_string(),
+            owner_id: format!("{:016x}", rand::random::<u64>()),
+            storage: StorageBackend::String,
+            cache: None,
+            stale_cache: None,
+            cache_pubsub: false,
+        }).unwrap();
+
+        let set = Command::Set { path: "a/b".to_string(), value: r#"{"x": 1}"#.to_string() };
+        run(set, &registry).await.unwrap();
+        run(Command::Get { path: "a/b".to_string() }, &registry).await.unwrap();
+        run(Command::Scan { path: "a".to_string() }, &registry).await.unwrap();
+        let missing = run(Command::Get { path: "a/missing".to_string() }, &registry).await.unwrap_err();
+        assert!(missing.contains("Key not found"), "{}", missing);
+        let invalid = Command::Set { path: "a/c".to_string(), value: "not json".to_string() };
+        assert!(run(invalid, &registry).await.unwrap_err().starts_with("Invalid JSON value"));
+
+        let output = std::env::temp_dir().join(format!("registry-backup-{:016x}.ndjson", rand::random::<u64>()));
+        run(Command::Backup { path: "a".to_string(), output: Some(output.clone()) }, &registry).await.unwrap();
+        let backup = std::fs::read_to_string(&output).unwrap();
+        std::fs::remove_file(&output).unwrap();
+        assert_eq!(backup, "{\"key\":\"b\",\"value\":{\"x\":1}}\n");
+    }
+}
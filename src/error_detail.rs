@@ -0,0 +1,181 @@
+// error_detail.rs
+use rand::Rng;
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::http::{ContentType, Header, Status, StatusClass};
+use rocket::serde::json::Value as JsonValue;
+use rocket::{Data, Request, Response};
+use std::io::Cursor;
+
+use crate::redis_registry_api::ErrorResponse;
+use crate::registry_error::code_for_status;
+use crate::settings;
+
+// =======================================================
+// Request ids and error detail verbosity
+// =======================================================
+// Raw Redis errors can mention internal hostnames; with ERROR_VERBOSITY=generic they are only logged,
+// and clients receive a fixed message with the request id to correlate with the logs
+
+/// Header carrying the request id, taken from the request when the client sent a valid one
+pub const REQUEST_ID_HEADER: &str = "X-Request-Id";
+
+// Longest request id accepted from clients
+const MAX_REQUEST_ID_LEN: usize = 64;
+
+/// Level of detail of server error responses
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorVerbosity {
+    /// Return the detailed error message (default)
+    Full,
+    /// Return a fixed message with the request id, logging the detailed error server-side
+    Generic,
+}
+
+impl ErrorVerbosity {
+    /// Read the ERROR_VERBOSITY environment variable, on every response so that it can be reloaded
+    pub fn from_env() -> Self {
+        match settings::var("ERROR_VERBOSITY").ok().as_deref().map(str::to_lowercase).as_deref() {
+            Some("generic") => ErrorVerbosity::Generic,
+            Some("full") | None => ErrorVerbosity::Full,
+            Some(other) => {
+                warn!("Invalid ERROR_VERBOSITY value: {}. Using full", other);
+                ErrorVerbosity::Full
+            }
+        }
+    }
+}
+
+/// Id of the current request, kept in the request-local cache
+pub struct RequestId(pub String);
+
+impl RequestId {
+    /// Get the id of a request, assigned by the fairing before routing
+    pub fn of(request: &Request<'_>) -> String {
+        request.local_cache(|| RequestId(generate_request_id())).0.clone()
+    }
+}
+
+fn generate_request_id() -> String {
+    format!("{:016x}", rand::thread_rng().gen::<u64>())
+}
+
+fn is_valid_request_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_REQUEST_ID_LEN
+        && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Check whether the detail of an error response is replaced by a generic message
+/// 503 is only returned with fixed messages (maintenance mode), which are safe to show
+fn hides_detail(status: Status, verbosity: ErrorVerbosity) -> bool {
+    status.class() == StatusClass::ServerError && status != Status::ServiceUnavailable && verbosity == ErrorVerbosity::Generic
+}
+
+/// Body of an error response: a generic message keeping the code of the detailed body, or the detailed body
+/// with the code of its status added when it has none; other fields (e.g. batch-cas conflicts) are kept
+fn error_body(status: Status, generic: bool, detail: &str, id: &str) -> String {
+    if generic {
+        let code = serde_json::from_str::<JsonValue>(detail)
+            .ok()
+            .and_then(|body| body.get("code").and_then(JsonValue::as_str).map(str::to_string))
+            .unwrap_or_else(|| code_for_status(status).to_string());
+        return serde_json::to_string(&ErrorResponse::with_code(&code, format!("Internal error (request id: {})", id))).unwrap_or_default();
+    }
+
+    match serde_json::from_str::<JsonValue>(detail) {
+        Ok(JsonValue::Object(mut body)) if body.contains_key("error") && !body.contains_key("code") => {
+            body.insert("code".to_string(), JsonValue::from(code_for_status(status)));
+            JsonValue::Object(body).to_string()
+        }
+        _ => detail.to_string(),
+    }
+}
+
+/// Fairing assigning every request an id, returned in the X-Request-Id header, adding the code of their
+/// status to error bodies without one, and replacing the body of server errors with a generic message
+/// when ERROR_VERBOSITY=generic
+pub struct ErrorDetailFairing;
+
+#[rocket::async_trait]
+impl Fairing for ErrorDetailFairing {
+    fn info(&self) -> Info {
+        Info {
+            name: "Request ids and error detail",
+            kind: Kind::Request | Kind::Response,
+        }
+    }
+
+    async fn on_request(&self, request: &mut Request<'_>, _data: &mut Data<'_>) {
+        let id = request
+            .headers()
+            .get_one(REQUEST_ID_HEADER)
+            .filter(|id| is_valid_request_id(id))
+            .map(str::to_string)
+            .unwrap_or_else(generate_request_id);
+        request.local_cache(|| RequestId(id));
+    }
+
+    async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
+        let id = RequestId::of(request);
+        response.set_header(Header::new(REQUEST_ID_HEADER, id.clone()));
+
+        let status = response.status();
+        if !matches!(status.class(), StatusClass::ClientError | StatusClass::ServerError) {
+            return;
+        }
+
+        let generic = hides_detail(status, ErrorVerbosity::from_env());
+        let is_json = response.content_type().is_some_and(|content_type| content_type.is_json());
+        if !is_json && !generic {
+            return;
+        }
+
+        let detail = response.body_mut().to_string().await.unwrap_or_default();
+        if generic {
+            error!("Request {} {} {} failed with {}: {}", id, request.method(), request.uri().path(), status, detail);
+        }
+        let body = error_body(status, generic, &detail, &id);
+        response.set_header(ContentType::JSON);
+        response.set_sized_body(body.len(), Cursor::new(body));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn parse(body: &str) -> JsonValue {
+        serde_json::from_str(body).unwrap()
+    }
+
+    #[test]
+    fn only_server_errors_are_hidden_with_generic_verbosity() {
+        assert!(hides_detail(Status::InternalServerError, ErrorVerbosity::Generic));
+        assert!(hides_detail(Status::GatewayTimeout, ErrorVerbosity::Generic));
+        assert!(!hides_detail(Status::ServiceUnavailable, ErrorVerbosity::Generic));
+        assert!(!hides_detail(Status::NotFound, ErrorVerbosity::Generic));
+        assert!(!hides_detail(Status::InternalServerError, ErrorVerbosity::Full));
+    }
+
+    #[test]
+    fn generic_bodies_keep_the_code_but_not_the_detail() {
+        let detail = r#"{"error": "Connection refused (os error 111) at redis-internal:6379", "code": "redis"}"#;
+        let body = parse(&error_body(Status::InternalServerError, true, detail, "abc123"));
+        assert_eq!(body, json!({"error": "Internal error (request id: abc123)", "code": "redis"}));
+
+        let body = parse(&error_body(Status::InternalServerError, true, "plain text", "abc123"));
+        assert_eq!(body["code"], json!("internal"));
+        assert!(!body.to_string().contains("plain text"));
+    }
+
+    #[test]
+    fn full_bodies_keep_the_detail_and_gain_a_code() {
+        let body = parse(&error_body(Status::NotFound, false, r#"{"error": "Key not found"}"#, "abc123"));
+        assert_eq!(body, json!({"error": "Key not found", "code": "not_found"}));
+
+        let detail = r#"{"error": "Version does not match", "code": "precondition_failed", "conflicts": []}"#;
+        assert_eq!(parse(&error_body(Status::PreconditionFailed, false, detail, "abc123")), parse(detail));
+        assert_eq!(error_body(Status::InternalServerError, false, "[1]", "abc123"), "[1]");
+    }
+}
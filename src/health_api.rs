@@ -0,0 +1,222 @@
+// health_api.rs
+use rocket::http::Status;
+use rocket::response::status;
+use rocket::serde::json::Json;
+use rocket::{get, routes, Route, State};
+use serde::Serialize;
+use std::collections::HashMap;
+use utoipa::{OpenApi, ToSchema};
+
+use crate::redis_registry::AsyncRegistry;
+use crate::redis_registry_api::ErrorResponse;
+use crate::auth::ApiKey;
+use crate::startup_gate::StartupGate;
+
+// =======================================================
+// OpenAPI Documentation
+// =======================================================
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        health_handler,
+        health_detail_handler
+    ),
+    components(
+        schemas(HealthDetail, RedisMemoryInfo)
+    ),
+    tags(
+        (name = "health", description = "Health API")
+    )
+)]
+pub struct HealthApiDoc;
+
+// =======================================================
+// Response Types
+// =======================================================
+
+/// Memory statistics reported by Redis `INFO memory`
+#[derive(Debug, Default, Serialize, ToSchema)]
+pub struct RedisMemoryInfo {
+    /// Bytes allocated by Redis
+    pub used_memory: Option<u64>,
+    /// Peak of used_memory
+    pub used_memory_peak: Option<u64>,
+    /// Bytes allocated as seen by the operating system
+    pub used_memory_rss: Option<u64>,
+    /// Configured memory limit (0: no limit)
+    pub maxmemory: Option<u64>,
+    /// Eviction policy applied when maxmemory is reached
+    pub maxmemory_policy: Option<String>,
+    /// Ratio of used_memory_rss to used_memory
+    pub mem_fragmentation_ratio: Option<f64>,
+}
+
+impl RedisMemoryInfo {
+    /// Parse the `field:value` lines of Redis `INFO memory`, ignoring unknown fields
+    pub fn parse(info: &str) -> Self {
+        let fields: HashMap<&str, &str> = info
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .filter_map(|line| line.trim().split_once(':'))
+            .collect();
+
+        let number = |name: &str| fields.get(name).and_then(|value| value.parse::<u64>().ok());
+
+        RedisMemoryInfo {
+            used_memory: number("used_memory"),
+            used_memory_peak: number("used_memory_peak"),
+            used_memory_rss: number("used_memory_rss"),
+            maxmemory: number("maxmemory"),
+            maxmemory_policy: fields.get("maxmemory_policy").map(|value| value.to_string()),
+            mem_fragmentation_ratio: fields.get("mem_fragmentation_ratio").and_then(|value| value.parse::<f64>().ok()),
+        }
+    }
+}
+
+/// Detailed health report for human operators
+#[derive(Debug, Serialize, ToSchema)]
+pub struct HealthDetail {
+    /// Redis round-trip latency measured with PING, in milliseconds
+    pub latency_ms: f64,
+    /// Number of keys in the owner namespace
+    pub key_count: usize,
+    pub memory: RedisMemoryInfo,
+}
+
+// =======================================================
+// REST API Handlers
+// =======================================================
+
+/// Report whether Redis is reachable (for liveness and readiness probes)
+#[utoipa::path(
+    get,
+    path = "/health",
+    tag = "health",
+    security(()),
+    responses(
+        (status = 200, description = "Redis is reachable", body = String),
+        (status = 503, description = "Redis is unreachable, or has not been reached since startup (\"initializing\" with STARTUP_GATE)", body = ErrorResponse)
+    )
+)]
+#[get("/")]
+pub async fn health_handler(registry: &State<AsyncRegistry>, gate: &State<StartupGate>)
+                            -> Result<status::Custom<String>, status::Custom<Json<ErrorResponse>>> {
+    trace!("Health request received");
+
+    match registry.ping().await {
+        Ok(_) => {
+            gate.mark_ready();
+            Ok(status::Custom(Status::Ok, "OK".to_string()))
+        },
+        Err(e) if !gate.is_ready() => {
+            debug!("Health check during startup: {}", e);
+            Err(status::Custom(Status::ServiceUnavailable, Json(ErrorResponse::new("initializing".to_string()))))
+        },
+        Err(e) => {
+            error!("Health check failed: {}", e);
+            Err(status::Custom(Status::ServiceUnavailable, Json(ErrorResponse::new(e.to_string()))))
+        },
+    }
+}
+
+/// Report Redis latency, the owner's key count and Redis memory statistics
+#[utoipa::path(
+    get,
+    path = "/health/detail",
+    tag = "health",
+    responses(
+        (status = 200, description = "Detailed health report", body = HealthDetail),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 503, description = "Redis is unreachable", body = ErrorResponse)
+    )
+)]
+#[get("/detail")]
+pub async fn health_detail_handler(api_key: ApiKey, registry: &State<AsyncRegistry>)
+                                   -> Result<Json<HealthDetail>, status::Custom<Json<ErrorResponse>>> {
+    debug!("Health detail request received");
+    let span = info_span!("health_detail_handler", identity = %api_key.identity());
+    let _guard = span.enter();
+
+    let unavailable = |e: redis::RedisError| {
+        error!("Health detail check failed: {}", e);
+        status::Custom(Status::ServiceUnavailable, Json(ErrorResponse::new(e.to_string())))
+    };
+
+    let latency = registry.ping().await.map_err(unavailable)?;
+    let key_count = registry.key_count().await.map_err(unavailable)?;
+    let memory = RedisMemoryInfo::parse(&registry.info("memory").await.map_err(unavailable)?);
+
+    info!("Health detail: latency={:?}, key_count={}", latency, key_count);
+    Ok(Json(HealthDetail {
+        latency_ms: latency.as_secs_f64() * 1000.0,
+        key_count,
+        memory,
+    }))
+}
+
+// =======================================================
+// Route Definitions
+// =======================================================
+
+// Function to get health routes
+pub fn routes() -> Vec<Route> {
+    routes![
+        health_handler,
+        health_detail_handler
+    ]
+}
+
+// Function to mount health routes
+pub fn mount_health_routes(rocket: rocket::Rocket<rocket::Build>) -> rocket::Rocket<rocket::Build> {
+    rocket.mount("/health", routes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rocket::local::asynchronous::Client;
+    use crate::redis_registry::{RegistryConfig, StorageBackend};
+
+    #[test]
+    fn memory_info_parses_known_fields() {
+        let info = "# Memory\r\nused_memory:1024\r\nused_memory_peak:2048\r\nmaxmemory:0\r\n\
+                    maxmemory_policy:noeviction\r\nmem_fragmentation_ratio:1.25\r\nunknown_field:x\r\n";
+        let memory = RedisMemoryInfo::parse(info);
+        assert_eq!(memory.used_memory, Some(1024));
+        assert_eq!(memory.used_memory_peak, Some(2048));
+        assert_eq!(memory.used_memory_rss, None);
+        assert_eq!(memory.maxmemory, Some(0));
+        assert_eq!(memory.maxmemory_policy.as_deref(), Some("noeviction"));
+        assert_eq!(memory.mem_fragmentation_ratio, Some(1.25));
+    }
+
+    #[rocket::async_test]
+    #[ignore = "requires a Redis server at REDIS_URL"]
+    async fn health_detail_reports_latency_key_count_and_memory() {
+        let registry = AsyncRegistry::new(&RegistryConfig {
+            owner_type: "test".to_string(),
+            owner_id: format!("{:016x}", rand::random::<u64>()),
+            storage: StorageBackend::String,
+            cache: None,
+            stale_cache: None,
+            cache_pubsub: false,
+        }).unwrap();
+        registry.set(&["a".to_string()], serde_json::json!(1)).await.unwrap();
+        let rocket = rocket::build().manage(registry).manage(StartupGate::new(false));
+        let client = Client::tracked(mount_health_routes(rocket)).await.unwrap();
+
+        let mut request = client.get("/health/detail");
+        if let Ok(token) = crate::settings::var("AUTH_TOKEN") {
+            request = request.header(rocket::http::Header::new("Authorization", format!("Bearer {}", token)));
+        }
+        let response = request.dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        let detail: serde_json::Value = response.into_json().await.unwrap();
+        assert!(detail["latency_ms"].as_f64().unwrap() >= 0.0);
+        assert_eq!(detail["key_count"], 1);
+        assert!(detail["memory"]["used_memory"].as_u64().unwrap() > 0);
+
+        assert_eq!(client.get("/health").dispatch().await.status(), Status::Ok);
+    }
+}
@@ -0,0 +1,71 @@
+// index_pruning.rs
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::tokio::time::sleep;
+use rocket::{Orbit, Rocket};
+use std::env;
+use std::time::Duration;
+
+use crate::redis_registry::AsyncRegistry;
+
+// =======================================================
+// Modification index pruning
+// =======================================================
+// Every write records its key in the modification index used by purge older_than. Keys that expire or are
+// removed outside the registry leave their entry behind, so the index is pruned of them periodically
+
+/// Default interval between two prunings of the modification index
+const DEFAULT_MTIME_PRUNE_INTERVAL_SECS: u64 = 3600;
+
+/// Read the pruning interval from MTIME_PRUNE_INTERVAL_SECS, None when pruning is disabled with 0
+fn prune_interval_from_env() -> Option<Duration> {
+    let seconds = match env::var("MTIME_PRUNE_INTERVAL_SECS").ok().map(|s| s.parse::<u64>()) {
+        Some(Ok(seconds)) => seconds,
+        Some(Err(e)) => {
+            warn!("Invalid MTIME_PRUNE_INTERVAL_SECS value: {}. Using {}", e, DEFAULT_MTIME_PRUNE_INTERVAL_SECS);
+            DEFAULT_MTIME_PRUNE_INTERVAL_SECS
+        }
+        None => DEFAULT_MTIME_PRUNE_INTERVAL_SECS,
+    };
+    (seconds > 0).then(|| Duration::from_secs(seconds))
+}
+
+/// Fairing pruning the modification index after launch and then at every interval
+pub struct IndexPruningFairing;
+
+#[rocket::async_trait]
+impl Fairing for IndexPruningFairing {
+    fn info(&self) -> Info {
+        Info {
+            name: "Modification index pruning",
+            kind: Kind::Liftoff,
+        }
+    }
+
+    async fn on_liftoff(&self, rocket: &Rocket<Orbit>) {
+        let Some(interval) = prune_interval_from_env() else {
+            info!("Modification index pruning is disabled");
+            return;
+        };
+        let Some(registry) = rocket.state::<AsyncRegistry>().cloned() else {
+            error!("AsyncRegistry is not managed by Rocket");
+            return;
+        };
+
+        let shutdown = rocket.shutdown();
+        rocket::tokio::spawn(async move {
+            loop {
+                rocket::tokio::select! {
+                    result = registry.prune_modified_index() => if let Err(e) = result {
+                        warn!("Failed to prune the modification index: {}", e);
+                    },
+                    _ = shutdown.clone() => return,
+                }
+
+                rocket::tokio::select! {
+                    _ = sleep(interval) => {},
+                    _ = shutdown.clone() => return,
+                }
+            }
+        });
+    }
+}
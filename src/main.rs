@@ -5,14 +5,36 @@ extern crate dotenv;
 
 mod redis_registry;
 mod redis_registry_api;
+mod admin_api;
+mod health_api;
+mod ws_api;
+mod read_cache;
+mod cache_sync;
+mod index_pruning;
 mod auth;
+mod cli;
 mod openapi;
+mod settings;
+mod maintenance;
+mod startup_gate;
+mod slow_requests;
+mod error_detail;
+mod registry_error;
+mod redact;
+#[cfg(feature = "s3")]
+mod s3_backup;
+mod transform;
+mod value_match;
+mod value_codec;
+mod wire_format;
+mod mirror;
 
 use std::env;
 use std::io;
 use rocket::http::Status;
 use rocket::response::status;
 use rocket::serde::json::Json;
+use clap::Parser;
 use dotenv::dotenv;
 use tracing_subscriber::{
     fmt,
@@ -20,11 +42,26 @@ use tracing_subscriber::{
     prelude::*,
     layer::SubscriberExt,
 };
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
 use tracing_appender::{non_blocking, rolling};
 
-use redis_registry::{AsyncRegistry, RegistryConfig};
+use redis_registry::{AsyncRegistry, RegistryConfig, StorageBackend};
+use read_cache::CacheConfig;
 use redis_registry_api::mount_routes;
+use admin_api::mount_admin_routes;
+use health_api::mount_health_routes;
+use ws_api::mount_ws_routes;
 use openapi::mount_swagger_ui;
+use settings::ConfigReloader;
+use auth::TokenScopes;
+use maintenance::{Maintenance, MaintenanceFairing};
+use startup_gate::{StartupGate, StartupGateFairing};
+use cache_sync::CacheSyncFairing;
+use index_pruning::IndexPruningFairing;
+use slow_requests::{SlowRequests, SlowRequestsFairing};
+use error_detail::ErrorDetailFairing;
+use redact::RedactingWriter;
+use cli::Cli;
 
 #[derive(Debug, serde::Serialize)]
 struct ApiError {
@@ -55,7 +92,25 @@ fn unauthorized() -> status::Custom<Json<ApiError>> {
     }))
 }
 
-fn setup_logging() -> io::Result<()> {
+#[catch(403)]
+fn forbidden() -> status::Custom<Json<ApiError>> {
+    error!("Forbidden access attempt");
+    status::Custom(Status::Forbidden, Json(ApiError {
+        error: "Operation not allowed for this token.".to_string()
+    }))
+}
+
+#[catch(504)]
+fn gateway_timeout() -> status::Custom<Json<ApiError>> {
+    error!("Request deadline exceeded");
+    status::Custom(Status::GatewayTimeout, Json(ApiError {
+        error: "Request deadline exceeded.".to_string()
+    }))
+}
+
+/// Set up logging to the console and to daily log files
+/// Console logs go to stderr instead of stdout in command line mode, so they do not mix with printed results
+fn setup_logging(console_to_stderr: bool) -> io::Result<()> {
     // Get log level from environment variable or use default
     let log_level = env::var("RUST_LOG").unwrap_or_else(|_| "info".to_string());
 
@@ -76,15 +131,24 @@ fn setup_logging() -> io::Result<()> {
         GUARD = Some(_guard);
     }
 
-    // Create console layer for stdout
+    // Key paths listed in REDACT_PREFIXES are redacted by both writers
+    redact::load_redacted_prefixes();
+
+    // Create console layer for stdout (stderr in command line mode)
+    let console_writer = if console_to_stderr {
+        BoxMakeWriter::new(io::stderr)
+    } else {
+        BoxMakeWriter::new(io::stdout)
+    };
     let console_layer = fmt::layer()
         .with_target(true)
-        .with_ansi(true);
+        .with_ansi(true)
+        .with_writer(RedactingWriter(console_writer));
 
     // Create JSON-formatted file layer
     let file_layer = fmt::layer()
         .with_ansi(false)
-        .with_writer(non_blocking_appender)
+        .with_writer(RedactingWriter(non_blocking_appender))
         .json();
 
     // Create environment filter from log level
@@ -105,21 +169,17 @@ fn setup_logging() -> io::Result<()> {
 
 #[rocket::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Parse the command line: a subcommand runs a single operation instead of the server
+    let cli = Cli::parse();
+
     // Load environment variables from .env file
     dotenv().ok();
 
     // Set up logging
-    if let Err(e) = setup_logging() {
+    if let Err(e) = setup_logging(cli.command.is_some()) {
         eprintln!("Failed to initialize logging: {}", e);
     }
 
-    // Print configuration information
-    if let Ok(port) = env::var("ROCKET_PORT") {
-        info!("Server will start on port: {}", port);
-    } else {
-        info!("Server will start on default port 8000");
-    }
-
     // Get owner_type and owner_id from environment variables
     let owner_type = env::var("OWNER_TYPE").unwrap_or_else(|_| {
         warn!("OWNER_TYPE environment variable not set. Using 'default'");
@@ -131,6 +191,37 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         "default".to_string()
     });
 
+    // Run a command line operation and exit
+    if let Some(command) = cli.command {
+        let config = RegistryConfig {
+            owner_type,
+            owner_id,
+            storage: StorageBackend::from_env(),
+            cache: None,
+            stale_cache: None,
+            cache_pubsub: false,
+        };
+        let registry = match AsyncRegistry::new(&config) {
+            Ok(registry) => registry,
+            Err(e) => {
+                eprintln!("Failed to initialize Redis registry: {}", e);
+                std::process::exit(1);
+            }
+        };
+        if let Err(e) = cli::run(command, &registry).await {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    // Print configuration information
+    if let Ok(port) = env::var("ROCKET_PORT") {
+        info!("Server will start on port: {}", port);
+    } else {
+        info!("Server will start on default port 8000");
+    }
+
     // Check for authentication token
     let auth_token = env::var("AUTH_TOKEN").unwrap_or_else(|_| {
         warn!("AUTH_TOKEN environment variable not set. API requests will not be authenticated!");
@@ -143,12 +234,28 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         info!("API endpoints are protected with bearer token authentication");
     }
 
-    info!("Registry initialized with owner_type={}, owner_id={}", owner_type, owner_id);
+    // Select how values are stored in Redis
+    let storage = StorageBackend::from_env();
+
+    info!("Registry initialized with owner_type={}, owner_id={}, storage={:?}", owner_type, owner_id, storage);
+
+    // Optional in-process read cache (enabled by CACHE_SIZE)
+    let cache = CacheConfig::from_env();
+    if cache.is_none() {
+        info!("Read cache is disabled");
+    }
+
+    // Optional cache of recently read values served during Redis outages (enabled by STALE_CACHE_SIZE)
+    let stale_cache = CacheConfig::stale_from_env();
 
     // Initialize the Redis registry
     let config = RegistryConfig {
         owner_type,
         owner_id,
+        storage,
+        cache,
+        stale_cache,
+        cache_pubsub: CacheConfig::pubsub_from_env(),
     };
 
     let registry = match AsyncRegistry::new(&config) {
@@ -166,11 +273,35 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     info!("Starting Rocket application...");
     let rocket_app = rocket::build()
         .manage(registry)
-        .register("/", catchers![not_found, internal_error, unauthorized]);
+        .manage(ConfigReloader::new())
+        .manage(TokenScopes::from_env())
+        .manage(Maintenance::new())
+        .manage(StartupGate::from_env())
+        .manage(SlowRequests::from_env())
+        .attach(MaintenanceFairing)
+        .attach(StartupGateFairing)
+        .attach(CacheSyncFairing)
+        .attach(IndexPruningFairing)
+        .attach(ErrorDetailFairing)
+        .attach(SlowRequestsFairing)
+        .register("/", catchers![not_found, internal_error, unauthorized, forbidden, gateway_timeout]);
 
     // Mount Redis registry routes
     let rocket_app = mount_routes(rocket_app);
 
+    // Mount WebSocket routes
+    let rocket_app = mount_ws_routes(rocket_app);
+
+    // Mount admin routes
+    let rocket_app = mount_admin_routes(rocket_app);
+
+    // Mount S3 backup routes (only built with the s3 feature)
+    #[cfg(feature = "s3")]
+    let rocket_app = s3_backup::mount_s3_backup_routes(rocket_app);
+
+    // Mount health routes
+    let rocket_app = mount_health_routes(rocket_app);
+
     // Mount Swagger UI
     let rocket_app = mount_swagger_ui(rocket_app);
 
@@ -0,0 +1,99 @@
+// maintenance.rs
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::http::{ContentType, Header, Status};
+use rocket::{Request, Response};
+use serde::Serialize;
+use std::io::Cursor;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use utoipa::ToSchema;
+
+use crate::redis_registry_api::ErrorResponse;
+
+// =======================================================
+// Global maintenance mode
+// =======================================================
+
+/// Default delay suggested to clients in the Retry-After header, in seconds
+pub const DEFAULT_RETRY_AFTER_SECS: u64 = 60;
+
+/// Routes rejected while maintenance mode is enabled
+const BLOCKED_PREFIX: &str = "/registry/";
+
+/// Maintenance mode toggled through POST /admin/maintenance, managed by Rocket
+pub struct Maintenance {
+    enabled: AtomicBool,
+    retry_after_secs: AtomicU64,
+}
+
+/// Current maintenance mode
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MaintenanceStatus {
+    /// Whether registry routes are rejected with 503
+    pub enabled: bool,
+    /// Delay suggested to clients in the Retry-After header, in seconds
+    pub retry_after: u64,
+}
+
+// Marker left in the request-local cache when a request was rejected for maintenance
+struct Rejected(bool);
+
+impl Maintenance {
+    pub fn new() -> Self {
+        Maintenance {
+            enabled: AtomicBool::new(false),
+            retry_after_secs: AtomicU64::new(DEFAULT_RETRY_AFTER_SECS),
+        }
+    }
+
+    /// Enable or disable maintenance mode
+    pub fn set(&self, enabled: bool, retry_after_secs: u64) {
+        self.retry_after_secs.store(retry_after_secs, Ordering::Relaxed);
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    pub fn status(&self) -> MaintenanceStatus {
+        MaintenanceStatus {
+            enabled: self.enabled.load(Ordering::Relaxed),
+            retry_after: self.retry_after_secs.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Check whether a request must be rejected because maintenance mode is enabled
+    /// Only registry routes are blocked; admin and health routes keep working
+    pub fn rejects(&self, request: &Request<'_>) -> bool {
+        if !self.enabled.load(Ordering::Relaxed) || !request.uri().path().starts_with(BLOCKED_PREFIX) {
+            return false;
+        }
+        request.local_cache(|| Rejected(true));
+        true
+    }
+}
+
+/// Response fairing turning the 503 of requests rejected for maintenance into our error body with a Retry-After header
+pub struct MaintenanceFairing;
+
+#[rocket::async_trait]
+impl Fairing for MaintenanceFairing {
+    fn info(&self) -> Info {
+        Info {
+            name: "Maintenance mode",
+            kind: Kind::Response,
+        }
+    }
+
+    async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
+        if !request.local_cache(|| Rejected(false)).0 {
+            return;
+        }
+        let Some(maintenance) = request.rocket().state::<Maintenance>() else {
+            return;
+        };
+
+        let body = serde_json::to_string(&ErrorResponse::new("Service is in maintenance mode".to_string()))
+            .unwrap_or_default();
+        response.set_status(Status::ServiceUnavailable);
+        response.set_header(ContentType::JSON);
+        response.set_header(Header::new("Retry-After", maintenance.status().retry_after.to_string()));
+        response.set_sized_body(body.len(), Cursor::new(body));
+    }
+}
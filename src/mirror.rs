@@ -0,0 +1,77 @@
+// mirror.rs
+use redis::Client;
+use rocket::tokio::sync::mpsc::{self, error::TrySendError};
+use std::sync::OnceLock;
+
+// =======================================================
+// Write mirroring
+// =======================================================
+// Successful writes are replayed on the mirror Redis (REDIS_MIRROR_URL) by a single background task over one
+// connection, so the mirror applies them in the order they were made on the primary
+
+/// Number of writes waiting for the mirror before further writes are dropped (a resync repairs the mirror)
+const MIRROR_QUEUE_SIZE: usize = 10_000;
+
+/// Mirror Redis with the queue of writes still to apply to it
+pub struct Mirror {
+    client: Client,
+    queue: OnceLock<mpsc::Sender<redis::Pipeline>>,
+}
+
+impl Mirror {
+    pub fn new(client: Client) -> Self {
+        Mirror { client, queue: OnceLock::new() }
+    }
+
+    /// Client of the mirror Redis, for operations that bypass the queue such as a resync
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// Queue a successful write for the mirror, starting the mirroring task on first use
+    /// Never waits: when the mirror falls too far behind, the write is dropped and logged
+    pub fn send(&self, pipe: redis::Pipeline) {
+        let queue = self.queue.get_or_init(|| {
+            let (sender, receiver) = mpsc::channel(MIRROR_QUEUE_SIZE);
+            rocket::tokio::spawn(apply_writes(self.client.clone(), receiver));
+            sender
+        });
+
+        match queue.try_send(pipe) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => warn!("Mirror queue is full, dropping a write; resync the mirror to repair it"),
+            Err(TrySendError::Closed(_)) => warn!("Mirroring task has stopped, dropping a write"),
+        }
+    }
+}
+
+/// Apply the queued writes one after the other, reconnecting after a failure
+/// A write that fails is logged and not retried
+async fn apply_writes(client: Client, mut receiver: mpsc::Receiver<redis::Pipeline>) {
+    let mut conn = None;
+    while let Some(pipe) = receiver.recv().await {
+        if conn.is_none() {
+            match client.get_multiplexed_async_connection().await {
+                Ok(c) => conn = Some(c),
+                Err(e) => {
+                    warn!("Failed to mirror write to secondary Redis: {}", e);
+                    continue;
+                }
+            }
+        }
+        let Some(c) = conn.as_mut() else {
+            continue;
+        };
+
+        match pipe.query_async::<()>(c).await {
+            Ok(_) => trace!("Write mirrored to secondary Redis"),
+            Err(e) => {
+                warn!("Failed to mirror write to secondary Redis: {}", e);
+                if e.is_connection_dropped() || e.is_io_error() {
+                    conn = None;
+                }
+            }
+        }
+    }
+    debug!("Mirror queue closed, mirroring task stopped");
+}
@@ -6,11 +6,17 @@ use rocket::Rocket;
 use rocket::Build;
 
 use crate::redis_registry_api::ApiDoc;
+use crate::admin_api::AdminApiDoc;
+use crate::health_api::HealthApiDoc;
 
 /// Configure the OpenAPI document with security requirements
 pub fn configure_openapi() -> utoipa::openapi::OpenApi {
     // Get the generated OpenAPI document
     let mut doc = ApiDoc::openapi();
+    doc.merge(AdminApiDoc::openapi());
+    doc.merge(HealthApiDoc::openapi());
+    #[cfg(feature = "s3")]
+    doc.merge(crate::s3_backup::S3BackupApiDoc::openapi());
 
     // Add security scheme (Bearer authentication)
     let security_scheme = SecurityScheme::Http(
@@ -18,7 +24,7 @@ pub fn configure_openapi() -> utoipa::openapi::OpenApi {
     );
 
     // Take the existing components or create new ones
-    let mut components = doc.components.take().unwrap_or_else(|| utoipa::openapi::Components::default());
+    let mut components = doc.components.take().unwrap_or_default();
 
     // Add the security scheme
     components.security_schemes.insert("bearer_auth".to_string(), security_scheme);
@@ -0,0 +1,441 @@
+// read_cache.rs
+use rocket::serde::json::Value as JsonValue;
+use serde::Serialize;
+use utoipa::ToSchema;
+use std::collections::HashMap;
+use std::env;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::redis_registry::{key_separator, ValueMetadata};
+use crate::settings;
+
+// =======================================================
+// In-process LRU cache for hot reads
+// =======================================================
+
+#[derive(Debug, Clone)]
+pub struct CacheConfig {
+    pub max_entries: usize,
+    pub ttl: Duration,
+}
+
+impl CacheConfig {
+    /// Read the cache configuration from environment variables
+    /// The cache is opt-in: it is only enabled when CACHE_SIZE is set to a positive number
+    pub fn from_env() -> Option<Self> {
+        let max_entries = match env::var("CACHE_SIZE").ok().map(|s| s.parse::<usize>()) {
+            Some(Ok(size)) if size > 0 => size,
+            Some(Ok(_)) | None => return None,
+            Some(Err(e)) => {
+                warn!("Invalid CACHE_SIZE value: {}. Read cache disabled", e);
+                return None;
+            }
+        };
+
+        Some(CacheConfig {
+            max_entries,
+            ttl: Self::ttl_from_env(),
+        })
+    }
+
+    /// Read the stale-read cache configuration from STALE_CACHE_SIZE and STALE_CACHE_MAX_AGE_MS
+    /// The stale cache is opt-in: it is only enabled when STALE_CACHE_SIZE is set to a positive number
+    pub fn stale_from_env() -> Option<Self> {
+        let max_entries = match env::var("STALE_CACHE_SIZE").ok().map(|s| s.parse::<usize>()) {
+            Some(Ok(size)) if size > 0 => size,
+            Some(Ok(_)) | None => return None,
+            Some(Err(e)) => {
+                warn!("Invalid STALE_CACHE_SIZE value: {}. Stale reads disabled", e);
+                return None;
+            }
+        };
+
+        let max_age_ms = match env::var("STALE_CACHE_MAX_AGE_MS").ok().map(|s| s.parse::<u64>()) {
+            Some(Ok(ms)) => ms,
+            Some(Err(e)) => {
+                warn!("Invalid STALE_CACHE_MAX_AGE_MS value: {}. Using default 300000", e);
+                300_000
+            }
+            None => 300_000,
+        };
+
+        Some(CacheConfig {
+            max_entries,
+            ttl: Duration::from_millis(max_age_ms),
+        })
+    }
+
+    /// Read the cache entry lifetime from the CACHE_TTL_MS environment variable
+    pub fn ttl_from_env() -> Duration {
+        let ttl_ms = match settings::var("CACHE_TTL_MS").ok().map(|s| s.parse::<u64>()) {
+            Some(Ok(ms)) => ms,
+            Some(Err(e)) => {
+                warn!("Invalid CACHE_TTL_MS value: {}. Using default 1000", e);
+                1000
+            }
+            None => 1000,
+        };
+        Duration::from_millis(ttl_ms)
+    }
+
+    /// Read the CACHE_PUBSUB environment variable (default: false)
+    /// When enabled, writes are published on a Redis channel and every instance evicts the changed keys from its caches
+    pub fn pubsub_from_env() -> bool {
+        env::var("CACHE_PUBSUB")
+            .map(|value| value.eq_ignore_ascii_case("true") || value == "1")
+            .unwrap_or(false)
+    }
+}
+
+/// Whether a read was served from the cache
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheStatus {
+    Hit,
+    Miss,
+    /// Served from the stale cache because Redis is unreachable
+    Stale,
+}
+
+impl CacheStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CacheStatus::Hit => "hit",
+            CacheStatus::Miss => "miss",
+            CacheStatus::Stale => "stale",
+        }
+    }
+}
+
+/// Counters of a read cache since startup
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct CacheStats {
+    /// Maximum number of entries
+    pub capacity: usize,
+    /// Number of entries currently held, including expired ones not evicted yet
+    pub entries: usize,
+    /// Reads served from the cache
+    pub hits: u64,
+    /// Reads of keys that were not cached or had expired
+    pub misses: u64,
+    /// Entries dropped because their key was written, locally or by another instance
+    pub invalidations: u64,
+}
+
+struct CacheEntry {
+    value: JsonValue,
+    // Content type and version of the value, once a get has read them
+    metadata: Option<ValueMetadata>,
+    stored_at: Instant,
+    expires_at: Instant,
+    last_used: u64,
+}
+
+struct CacheState {
+    entries: HashMap<String, CacheEntry>,
+    // Monotonic counter used to find the least recently used entry
+    tick: u64,
+    // Counter bumped by every invalidation; readers take it before reading Redis (see generation)
+    generation: u64,
+    // Generation of the last invalidation of each key invalidated since `floor`
+    invalidated: HashMap<String, u64>,
+    // Values read before this generation are never inserted: it is raised by prefix invalidations,
+    // which do not record every key, and when `invalidated` is trimmed
+    floor: u64,
+}
+
+impl CacheState {
+    /// Whether a key was invalidated after the given generation was taken
+    fn invalidated_since(&self, key: &str, generation: u64) -> bool {
+        generation < self.floor || self.invalidated.get(key).is_some_and(|&invalidated| invalidated > generation)
+    }
+}
+
+pub struct ReadCache {
+    max_entries: usize,
+    // Entry lifetime in milliseconds, reloadable at runtime
+    ttl_ms: AtomicU64,
+    state: Mutex<CacheState>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    invalidations: AtomicU64,
+}
+
+impl ReadCache {
+    pub fn new(config: CacheConfig) -> Self {
+        info!(
+            "Read cache enabled with max_entries={}, ttl={:?}",
+            config.max_entries, config.ttl
+        );
+        ReadCache {
+            max_entries: config.max_entries,
+            ttl_ms: AtomicU64::new(config.ttl.as_millis() as u64),
+            state: Mutex::new(CacheState {
+                entries: HashMap::new(),
+                tick: 0,
+                generation: 0,
+                invalidated: HashMap::new(),
+                floor: 0,
+            }),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            invalidations: AtomicU64::new(0),
+        }
+    }
+
+    /// Maximum number of entries the cache holds
+    pub fn capacity(&self) -> usize {
+        self.max_entries
+    }
+
+    /// Report the size of the cache and its hit, miss and invalidation counters
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            capacity: self.max_entries,
+            entries: self.state.lock().unwrap().entries.len(),
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            invalidations: self.invalidations.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Change the lifetime of entries inserted from now on
+    pub fn set_ttl(&self, ttl: Duration) {
+        info!("Read cache TTL set to {:?}", ttl);
+        self.ttl_ms.store(ttl.as_millis() as u64, Ordering::Relaxed);
+    }
+
+    /// Build the cache key for the specified key parts
+    fn cache_key(parts: &[String]) -> String {
+        parts.join(key_separator())
+    }
+
+    /// Get a cached value that expired at most `max_stale` ago, together with its age
+    /// Expired entries are kept until they are evicted, so that more tolerant readers can still use them
+    pub fn get_within(&self, parts: &[String], max_stale: Duration) -> Option<(JsonValue, Duration)> {
+        let key = Self::cache_key(parts);
+        let mut state = self.state.lock().unwrap();
+        state.tick += 1;
+        let tick = state.tick;
+
+        let now = Instant::now();
+        match state.entries.get_mut(&key) {
+            Some(entry) if entry.expires_at + max_stale > now => {
+                entry.last_used = tick;
+                trace!("Read cache hit for key: {}", key);
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                Some((entry.value.clone(), now.duration_since(entry.stored_at)))
+            }
+            Some(_) => {
+                trace!("Read cache entry expired for key: {}", key);
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+            None => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+        }
+    }
+
+    /// Current generation of the cache, to be taken before reading a value from Redis and passed to insert
+    pub fn generation(&self) -> u64 {
+        self.state.lock().unwrap().generation
+    }
+
+    /// Store a value read at the given generation, evicting the least recently used entry if the cache is full
+    /// The value is dropped if its key was invalidated since, as it may predate the write that invalidated it
+    pub fn insert(&self, parts: &[String], value: JsonValue, generation: u64) {
+        let key = Self::cache_key(parts);
+        let mut state = self.state.lock().unwrap();
+        if state.invalidated_since(&key, generation) {
+            trace!("Not caching value of key {}, invalidated while it was read", key);
+            return;
+        }
+        state.tick += 1;
+        let tick = state.tick;
+
+        if !state.entries.contains_key(&key) && state.entries.len() >= self.max_entries {
+            let now = Instant::now();
+            state.entries.retain(|_, entry| entry.expires_at > now);
+
+            if state.entries.len() >= self.max_entries {
+                let lru_key = state
+                    .entries
+                    .iter()
+                    .min_by_key(|(_, entry)| entry.last_used)
+                    .map(|(key, _)| key.clone());
+                if let Some(lru_key) = lru_key {
+                    trace!("Evicting least recently used cache entry: {}", lru_key);
+                    state.entries.remove(&lru_key);
+                }
+            }
+        }
+
+        let now = Instant::now();
+        state.entries.insert(key, CacheEntry {
+            value,
+            metadata: None,
+            stored_at: now,
+            expires_at: now + Duration::from_millis(self.ttl_ms.load(Ordering::Relaxed)),
+            last_used: tick,
+        });
+    }
+
+    /// Get the metadata cached with a value that has not expired yet
+    pub fn metadata(&self, parts: &[String]) -> Option<ValueMetadata> {
+        let key = Self::cache_key(parts);
+        let state = self.state.lock().unwrap();
+        state.entries.get(&key).filter(|entry| entry.expires_at > Instant::now()).and_then(|entry| entry.metadata.clone())
+    }
+
+    /// Store the metadata of a cached value read at the given generation
+    /// Does nothing if the value is no longer cached or its key was invalidated since
+    pub fn attach_metadata(&self, parts: &[String], metadata: ValueMetadata, generation: u64) {
+        let key = Self::cache_key(parts);
+        let mut state = self.state.lock().unwrap();
+        if state.invalidated_since(&key, generation) {
+            return;
+        }
+        if let Some(entry) = state.entries.get_mut(&key) {
+            entry.metadata = Some(metadata);
+        }
+    }
+
+    /// Drop the cached value of a single key and keep values read before from being inserted
+    pub fn invalidate(&self, parts: &[String]) {
+        let key = Self::cache_key(parts);
+        trace!("Invalidating read cache for key: {}", key);
+        let mut state = self.state.lock().unwrap();
+        state.generation += 1;
+        let generation = state.generation;
+        if state.entries.remove(&key).is_some() {
+            self.invalidations.fetch_add(1, Ordering::Relaxed);
+        }
+
+        // Only reads in flight need the record, so past the cache size all of them are refused instead
+        state.invalidated.insert(key, generation);
+        if state.invalidated.len() > self.max_entries {
+            state.invalidated.clear();
+            state.floor = generation;
+        }
+    }
+
+    /// Drop all cached values at or below the specified prefix and keep values read before from being inserted
+    pub fn invalidate_prefix(&self, parts: &[String]) {
+        let prefix = Self::cache_key(parts);
+        trace!("Invalidating read cache for prefix: {}", prefix);
+        let mut state = self.state.lock().unwrap();
+        state.generation += 1;
+        state.floor = state.generation;
+        state.invalidated.clear();
+        let before = state.entries.len();
+        if prefix.is_empty() {
+            state.entries.clear();
+        } else {
+            let nested = format!("{}{}", prefix, key_separator());
+            state.entries.retain(|key, _| key != &prefix && !key.starts_with(&nested));
+        }
+        self.invalidations.fetch_add((before - state.entries.len()) as u64, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn cache(max_entries: usize) -> ReadCache {
+        ReadCache::new(CacheConfig { max_entries, ttl: Duration::from_secs(60) })
+    }
+
+    fn parts(path: &str) -> Vec<String> {
+        path.split('/').map(str::to_string).collect()
+    }
+
+    #[test]
+    fn values_read_before_an_invalidation_are_not_cached() {
+        let cache = cache(10);
+        let generation = cache.generation();
+        cache.invalidate(&parts("a/b"));
+        cache.insert(&parts("a/b"), json!("old"), generation);
+        assert!(cache.get_within(&parts("a/b"), Duration::ZERO).is_none());
+
+        // Other keys and later reads are not affected
+        cache.insert(&parts("a/c"), json!(1), generation);
+        cache.insert(&parts("a/b"), json!("new"), cache.generation());
+        assert_eq!(cache.get_within(&parts("a/c"), Duration::ZERO).map(|(value, _)| value), Some(json!(1)));
+        assert_eq!(cache.get_within(&parts("a/b"), Duration::ZERO).map(|(value, _)| value), Some(json!("new")));
+    }
+
+    #[test]
+    fn values_read_before_a_prefix_invalidation_are_not_cached() {
+        let cache = cache(10);
+        let generation = cache.generation();
+        cache.invalidate_prefix(&parts("a"));
+        cache.insert(&parts("a/b"), json!(1), generation);
+        assert!(cache.get_within(&parts("a/b"), Duration::ZERO).is_none());
+    }
+
+    #[test]
+    fn trimmed_invalidations_still_refuse_older_reads() {
+        let cache = cache(2);
+        let generation = cache.generation();
+        for key in ["a", "b", "c"] {
+            cache.invalidate(&parts(key));
+        }
+        cache.insert(&parts("a"), json!(1), generation);
+        assert!(cache.get_within(&parts("a"), Duration::ZERO).is_none());
+    }
+
+    #[test]
+    fn expired_entries_are_only_served_within_max_stale() {
+        let cache = ReadCache::new(CacheConfig { max_entries: 10, ttl: Duration::ZERO });
+        cache.insert(&parts("a"), json!(1), cache.generation());
+        assert!(cache.get_within(&parts("a"), Duration::ZERO).is_none());
+        assert_eq!(cache.get_within(&parts("a"), Duration::from_secs(60)).map(|(value, _)| value), Some(json!(1)));
+    }
+
+    #[test]
+    fn the_least_recently_used_entry_is_evicted() {
+        let cache = cache(2);
+        cache.insert(&parts("a"), json!(1), cache.generation());
+        cache.insert(&parts("b"), json!(2), cache.generation());
+        assert!(cache.get_within(&parts("a"), Duration::ZERO).is_some());
+        cache.insert(&parts("c"), json!(3), cache.generation());
+
+        assert!(cache.get_within(&parts("a"), Duration::ZERO).is_some());
+        assert!(cache.get_within(&parts("b"), Duration::ZERO).is_none());
+        assert!(cache.get_within(&parts("c"), Duration::ZERO).is_some());
+        assert_eq!(cache.stats().entries, 2);
+    }
+
+    #[test]
+    fn prefix_invalidation_drops_the_prefix_and_its_descendants_only() {
+        let cache = cache(10);
+        for key in ["a", "a/b", "a/b/c", "ab"] {
+            cache.insert(&parts(key), json!(key), cache.generation());
+        }
+        cache.invalidate_prefix(&parts("a"));
+
+        assert!(cache.get_within(&parts("a"), Duration::ZERO).is_none());
+        assert!(cache.get_within(&parts("a/b/c"), Duration::ZERO).is_none());
+        assert!(cache.get_within(&parts("ab"), Duration::ZERO).is_some());
+        let stats = cache.stats();
+        assert_eq!((stats.invalidations, stats.hits, stats.misses), (3, 1, 2));
+    }
+
+    #[test]
+    fn metadata_is_dropped_with_its_value() {
+        let cache = cache(10);
+        let metadata = ValueMetadata { content_type: "text/plain".to_string(), version: 3, ttl: None };
+        cache.insert(&parts("a"), json!(1), cache.generation());
+        cache.attach_metadata(&parts("a"), metadata, cache.generation());
+        assert_eq!(cache.metadata(&parts("a")).map(|metadata| metadata.version), Some(3));
+
+        cache.invalidate(&parts("a"));
+        cache.insert(&parts("a"), json!(2), cache.generation());
+        assert!(cache.metadata(&parts("a")).is_none());
+    }
+}
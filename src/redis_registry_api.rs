@@ -1,13 +1,30 @@
 // registry_api.rs
-use rocket::http::Status;
-use rocket::response::status;
+use rocket::data::{ByteUnit, Data, Limits};
+use rocket::http::{ContentType, Status};
+use rocket::response::stream::TextStream;
+use rocket::response::{self, status, Responder};
+use rocket::request::{FromRequest, Outcome};
+use rocket::Request;
 use rocket::serde::json::{Json, Value as JsonValue};
-use rocket::{delete, get, post, routes, Route, State};
+use rocket::tokio::io::AsyncReadExt;
+use redis::RedisError;
+use rocket::tokio::time::Instant;
+use rocket::{delete, get, post, routes, Route};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::ops::Deref;
+use std::time::Duration;
 use utoipa::{OpenApi, ToSchema};
 
-use crate::redis_registry::AsyncRegistry;
+use crate::redis_registry::{flatten_tree, key_separator, now_ms, AsyncRegistry, BatchCasResult, ExpireCondition, PipelineOp, PipelineResult, RenameOutcome, DEFAULT_CONTENT_TYPE, PIPELINE_BATCH_SIZE, RESERVED_KEY_CHARACTERS};
+use crate::read_cache::CacheStatus;
+use crate::registry_error::RegistryError;
+use crate::settings;
 use crate::auth::ApiKey;
+use crate::maintenance::Maintenance;
+use crate::startup_gate::StartupGate;
+use crate::value_match::ValueMatch;
+use crate::wire_format::{accepts_msgpack, BodyEncoding, msgpack_content_type, to_msgpack, WireValue};
 
 // =======================================================
 // Response Types
@@ -16,6 +33,599 @@ use crate::auth::ApiKey;
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct ErrorResponse {
     pub error: String,
+    /// Machine-readable error category (e.g. not_found, conflict, timeout, validation, serialization, redis),
+    /// derived from the status code unless the failure was classified more precisely
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+}
+
+impl ErrorResponse {
+    /// Build an error body whose code is filled in from the response status (see ErrorDetailFairing)
+    pub fn new(error: String) -> Self {
+        ErrorResponse { error, code: None }
+    }
+
+    /// Build an error body with an explicit code
+    pub fn with_code(code: &str, error: String) -> Self {
+        ErrorResponse { error, code: Some(code.to_string()) }
+    }
+}
+
+/// A single operation of a pipeline request
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct PipelineRequestOp {
+    /// Operation name: get, set or delete
+    pub op: String,
+    /// Key path as a string (can be empty or nested using forward slashes like 'a/b/c')
+    #[serde(default)]
+    pub path: Option<String>,
+    /// Value to store, required for set (an explicit null stores null)
+    #[serde(default, deserialize_with = "present_value")]
+    pub value: Option<JsonValue>,
+}
+
+// Helper function to deserialize a field that is present, so that an explicit null is Some(Null) rather than None
+fn present_value<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Option<JsonValue>, D::Error> {
+    JsonValue::deserialize(deserializer).map(Some)
+}
+
+/// A single write of a batch-set request
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct BatchSetEntry {
+    /// Key path as a string (can be empty or nested using forward slashes like 'a/b/c')
+    #[serde(default)]
+    pub path: Option<String>,
+    /// Value to store
+    pub value: JsonValue,
+    /// Seconds until the key expires; omit to store it without an expiry
+    #[serde(default)]
+    pub ttl: Option<u64>,
+}
+
+/// Outcome of a batch-set
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BatchSetResponse {
+    /// Number of keys written
+    pub written: usize,
+    /// Number of written keys that expire
+    pub with_ttl: usize,
+}
+
+/// A single versioned write of a batch-cas request
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct BatchCasRequestEntry {
+    /// Key path as a string (can be empty or nested using forward slashes like 'a/b/c')
+    #[serde(default)]
+    pub path: Option<String>,
+    /// Version the key must have (X-Version of get, 0 if it was never written with a version)
+    pub if_version: u64,
+    /// Value to store
+    pub value: JsonValue,
+}
+
+/// Outcome of an applied batch-cas
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BatchCasResponse {
+    /// New version of every written key path
+    pub versions: BTreeMap<String, u64>,
+}
+
+/// Key whose version did not match the expected one
+#[derive(Debug, Serialize, ToSchema)]
+pub struct VersionConflict {
+    pub path: String,
+    /// Version given in if_version
+    pub expected: u64,
+    /// Current version of the key
+    pub current: u64,
+}
+
+/// Body of a batch-cas rejected because some versions did not match
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BatchCasConflictResponse {
+    pub error: String,
+    /// Keys whose versions did not match; nothing was written
+    pub conflicts: Vec<VersionConflict>,
+}
+
+/// Live registration returned by discover
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Registration {
+    pub value: JsonValue,
+    /// Remaining seconds until the registration expires without a heartbeat (-1: no expiry)
+    pub ttl: i64,
+}
+
+/// Outcome of a recursive delete
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RecursiveDeleteResponse {
+    /// Total number of deleted keys
+    pub deleted: i64,
+    /// Whether the key at the path itself existed
+    pub key_existed: bool,
+    /// Number of deleted keys below the path
+    pub descendants: i64,
+}
+
+/// Value of a key returned by batch-get-ttl with its remaining TTL
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ValueWithTtl {
+    pub value: JsonValue,
+    /// Remaining seconds until the key expires (-1: no expiry)
+    pub ttl: i64,
+}
+
+/// Outcome of a persist
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PersistResponse {
+    /// Whether a TTL was removed; false if the key had none
+    pub persisted: bool,
+}
+
+/// Memory used by a key, or by a key and its subtree with recursive=true
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MemoryUsageResponse {
+    /// Bytes used in Redis by the keys and their values, as reported by MEMORY USAGE
+    pub bytes: i64,
+    /// Number of keys measured
+    pub keys: usize,
+}
+
+/// Key of a prefix that expires first, returned by next-expiry
+#[derive(Debug, Serialize, ToSchema)]
+pub struct NextExpiry {
+    /// Relative key path
+    pub key: String,
+    /// Remaining seconds until the key expires
+    pub ttl: i64,
+}
+
+/// Outcome of a rotate
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RotateResponse {
+    /// The new current value
+    pub value: JsonValue,
+    /// Number of previous values retained in the history
+    pub history_length: usize,
+}
+
+/// Value of a key that differs between two prefixes
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ChangedValue {
+    /// Value below the first prefix (a)
+    pub old: JsonValue,
+    /// Value below the second prefix (b)
+    pub new: JsonValue,
+}
+
+/// Value of a key that differs between the registry and the expected state
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DriftedValue {
+    /// Value stored in the registry
+    pub stored: JsonValue,
+    /// Value in the expected state
+    pub expected: JsonValue,
+}
+
+/// Differences between the keys below a prefix and an expected state, keyed by relative key path
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DiffAgainstResponse {
+    /// Keys of the expected state that are not stored, with their expected values
+    pub missing: BTreeMap<String, JsonValue>,
+    /// Stored keys that are not part of the expected state, with their stored values
+    pub extra: BTreeMap<String, JsonValue>,
+    /// Keys whose stored value differs from the expected one
+    pub changed: BTreeMap<String, DriftedValue>,
+    /// Number of keys whose stored value matches the expected one
+    pub unchanged: usize,
+}
+
+/// Number of values per JSON type below a prefix
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TypeHistogramResponse {
+    /// Values per JSON type (object, array, string, number, boolean, null); types without values are left out
+    pub counts: BTreeMap<String, usize>,
+    /// Number of values whose type was counted
+    pub inspected: usize,
+    /// The prefix has more than 10000 keys and only the first 10000 were inspected
+    pub truncated: bool,
+}
+
+/// Immediate child of a prefix
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ChildEntry {
+    /// Path segment following the prefix
+    pub name: String,
+    /// A value is stored at the child's path itself
+    pub leaf: bool,
+    /// Further keys exist below the child
+    pub has_children: bool,
+}
+
+/// Differences between two prefixes, keyed by relative key path
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DiffResponse {
+    /// Keys only present below b, with their values
+    pub added: BTreeMap<String, JsonValue>,
+    /// Keys only present below a, with their values
+    pub removed: BTreeMap<String, JsonValue>,
+    /// Keys present below both prefixes with different values
+    pub changed: BTreeMap<String, ChangedValue>,
+    /// Number of keys present below both prefixes with equal values
+    pub unchanged: usize,
+}
+
+/// Outcome of a conditional purge
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PurgeWhereResponse {
+    /// Number of deleted keys (or keys that would be deleted in a dry run)
+    pub count: usize,
+    /// Relative key paths of the deleted keys
+    pub keys: Vec<String>,
+    pub dry_run: bool,
+}
+
+/// Current state of a rate counter
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RateCounter {
+    /// Number of increments within the current window
+    pub count: i64,
+    /// Remaining seconds of the current window (-2 when no window is running)
+    pub ttl: i64,
+}
+
+/// Outcome of a streaming restore
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RestoreStreamResponse {
+    /// Number of restored keys
+    pub restored: i64,
+    /// Line numbers (1-based) that were not valid `{"key", "value"}` objects and were skipped
+    pub failed_lines: Vec<usize>,
+}
+
+/// Value together with the JSON Schema registered for its path
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ValueWithSchema {
+    pub value: JsonValue,
+    /// Schema registered for the longest prefix of the path, null if none
+    pub schema: Option<JsonValue>,
+}
+
+/// Outcome of a restore with `report=changed`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RestoreChangedResponse {
+    /// Number of restored keys
+    pub restored: i64,
+    /// Relative key paths that did not exist or held a different value before the restore
+    pub changed: Vec<String>,
+}
+
+/// Outcome of a batch set-if-absent
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BatchSetNxResponse {
+    /// Relative key paths that did not exist and were written
+    pub created: Vec<String>,
+    /// Relative key paths that already existed and were left unchanged
+    pub skipped: Vec<String>,
+}
+
+/// Result of a single pipeline operation
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PipelineResponseOp {
+    pub ok: bool,
+    /// Value read by a get, or "OK" for a successful set or delete
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<JsonValue>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Wraps a response and adds an `X-Cache: hit|miss|stale` header when a cache is enabled
+/// Values served from the stale cache during a Redis outage also get an `X-Stale: true` header
+/// The age of the served value, when known, is reported in whole seconds in an `X-Cache-Age` header
+pub struct WithCacheStatus<R>(pub R, pub Option<CacheStatus>, pub Option<Duration>);
+
+impl<'r, 'o: 'r, R: Responder<'r, 'o>> Responder<'r, 'o> for WithCacheStatus<R> {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'o> {
+        let mut response = self.0.respond_to(request)?;
+        if let Some(cache_status) = self.1 {
+            response.set_raw_header("X-Cache", cache_status.as_str());
+            if cache_status == CacheStatus::Stale {
+                response.set_raw_header("X-Stale", "true");
+            }
+        }
+        if let Some(age) = self.2 {
+            response.set_raw_header("X-Cache-Age", age.as_secs().to_string());
+        }
+        Ok(response)
+    }
+}
+
+/// JSON response body, optionally pretty-printed with a trailing newline for human readers
+/// Encoded as MessagePack instead when the client sends `Accept: application/msgpack`
+pub struct JsonBody {
+    pub value: JsonValue,
+    pub pretty: bool,
+}
+
+impl<'r> Responder<'r, 'static> for JsonBody {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'static> {
+        if accepts_msgpack(request) {
+            return match to_msgpack(&self.value) {
+                Ok(body) => (msgpack_content_type(), body).respond_to(request),
+                Err(e) => {
+                    error!("Failed to encode MessagePack response: {}", e);
+                    Err(Status::InternalServerError)
+                }
+            };
+        }
+
+        if !self.pretty {
+            return Json(self.value).respond_to(request);
+        }
+
+        match serde_json::to_string_pretty(&self.value) {
+            Ok(body) => (ContentType::JSON, format!("{}\n", body)).respond_to(request),
+            Err(e) => {
+                error!("Failed to pretty-print JSON response: {}", e);
+                Err(Status::InternalServerError)
+            }
+        }
+    }
+}
+
+/// Value served with its stored content type
+/// String values with a non-JSON content type are sent as the raw string, everything else as JSON
+/// The TTL, when requested, is reported in an `X-TTL` header and the key's version in an `X-Version` header
+pub struct StoredValue {
+    pub body: JsonBody,
+    pub content_type: ContentType,
+    pub ttl: Option<i64>,
+    pub version: Option<u64>,
+}
+
+impl<'r> Responder<'r, 'static> for StoredValue {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'static> {
+        let mut response = if self.content_type.is_json() || accepts_msgpack(request) {
+            self.body.respond_to(request)?
+        } else if let JsonValue::String(text) = self.body.value {
+            (self.content_type, text).respond_to(request)?
+        } else {
+            let mut response = self.body.respond_to(request)?;
+            response.set_header(self.content_type);
+            response
+        };
+
+        if let Some(ttl) = self.ttl {
+            response.set_raw_header("X-TTL", ttl.to_string());
+        }
+        if let Some(version) = self.version {
+            response.set_raw_header("X-Version", version.to_string());
+        }
+        Ok(response)
+    }
+}
+
+/// Response of a dump: a single JSON object, or NDJSON lines streamed while the values are read with `format=ndjson`
+pub enum DumpOutcome {
+    Json(status::Custom<JsonBody>),
+    Ndjson(NdjsonDump),
+}
+
+/// Keys of a dump whose values are read in batches of PIPELINE_BATCH_SIZE and streamed as `{"key", "value"}` lines
+pub struct NdjsonDump {
+    registry: AsyncRegistry,
+    parts: Vec<String>,
+    keys: Vec<String>,
+}
+
+impl<'r> Responder<'r, 'r> for DumpOutcome {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'r> {
+        match self {
+            DumpOutcome::Json(response) => response.respond_to(request),
+            DumpOutcome::Ndjson(NdjsonDump { registry, parts, keys }) => {
+                let lines = TextStream! {
+                    let mut count = 0;
+                    for chunk in keys.chunks(PIPELINE_BATCH_SIZE) {
+                        match registry.read_entries(&parts, chunk.to_vec()).await {
+                            Ok(entries) => for (key, value) in entries {
+                                yield format!("{}\n", serde_json::json!({ "key": key, "value": value }));
+                                count += 1;
+                            },
+                            Err(e) => {
+                                // The status line is already sent, so the failure is reported as the last line
+                                error!("NDJSON dump with prefix {:?} failed after {} keys: {}", parts, count, e);
+                                yield format!("{}\n", serde_json::json!({ "error": e.to_string() }));
+                                return;
+                            },
+                        }
+                    }
+                    info!("Streamed {} keys with prefix: {:?}", count, parts);
+                };
+                (ContentType::new("application", "x-ndjson"), lines).respond_to(request)
+            },
+        }
+    }
+}
+
+/// Response of a restore: the plain number of restored keys, or a JSON report of the changed keys with `report=changed`
+pub enum RestoreOutcome {
+    Count(i64),
+    Changed(RestoreChangedResponse),
+}
+
+impl<'r> Responder<'r, 'static> for RestoreOutcome {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'static> {
+        match self {
+            RestoreOutcome::Count(count) => count.to_string().respond_to(request),
+            RestoreOutcome::Changed(report) => Json(report).respond_to(request),
+        }
+    }
+}
+
+/// Response of a delete: plain "OK" (or 204 with no_content), or a JSON breakdown of the deleted keys with `recursive=true`
+pub enum DeleteOutcome {
+    Plain(status::Custom<String>),
+    Recursive(RecursiveDeleteResponse),
+}
+
+impl<'r> Responder<'r, 'static> for DeleteOutcome {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'static> {
+        match self {
+            DeleteOutcome::Plain(response) => response.respond_to(request),
+            DeleteOutcome::Recursive(report) => Json(report).respond_to(request),
+        }
+    }
+}
+
+/// Failed batch-cas: 412 with the conflicting keys, or a plain error response
+pub enum BatchCasFailure {
+    Conflicts(BatchCasConflictResponse),
+    Error(status::Custom<Json<ErrorResponse>>),
+}
+
+impl<'r> Responder<'r, 'static> for BatchCasFailure {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'static> {
+        match self {
+            BatchCasFailure::Conflicts(conflicts) => status::Custom(Status::PreconditionFailed, Json(conflicts)).respond_to(request),
+            BatchCasFailure::Error(error) => error.respond_to(request),
+        }
+    }
+}
+
+// =======================================================
+// Request Guards
+// =======================================================
+
+/// Default size limit of NDJSON request bodies, overridable with the `ndjson` Rocket limit
+const NDJSON_LIMIT: ByteUnit = ByteUnit::Gibibyte(16);
+
+/// Header letting the admin token operate on another owner's keys (`<owner_type>/<owner_id>`)
+pub const OWNER_OVERRIDE_HEADER: &str = "X-Owner-Override";
+
+/// Header carrying the request deadline as a Unix timestamp in milliseconds
+pub const DEADLINE_HEADER: &str = "X-Request-Deadline";
+
+/// gRPC-style header carrying the request timeout relative to its arrival (e.g. `500m`, `2S`)
+pub const GRPC_TIMEOUT_HEADER: &str = "grpc-timeout";
+
+// Parse a grpc-timeout value: up to 8 digits followed by a unit (H, M, S, m, u or n)
+fn parse_grpc_timeout(value: &str) -> Option<Duration> {
+    let unit = value.chars().last()?;
+    let amount = &value[..value.len() - unit.len_utf8()];
+    if amount.is_empty() || amount.len() > 8 || !amount.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let amount: u64 = amount.parse().ok()?;
+    match unit {
+        'H' => Some(Duration::from_secs(amount * 3600)),
+        'M' => Some(Duration::from_secs(amount * 60)),
+        'S' => Some(Duration::from_secs(amount)),
+        'm' => Some(Duration::from_millis(amount)),
+        'u' => Some(Duration::from_micros(amount)),
+        'n' => Some(Duration::from_nanos(amount)),
+        _ => None,
+    }
+}
+
+/// Deadline of a request from its `X-Request-Deadline` or `grpc-timeout` header
+/// A malformed header is rejected with 400 and a deadline that has already passed with 504
+fn request_deadline(request: &Request<'_>) -> Result<Option<Instant>, Status> {
+    let remaining = if let Some(value) = request.headers().get_one(DEADLINE_HEADER) {
+        let Ok(deadline_ms) = value.trim().parse::<i64>() else {
+            warn!("Invalid {} header: {}", DEADLINE_HEADER, value);
+            return Err(Status::BadRequest);
+        };
+        let remaining_ms = deadline_ms - now_ms() as i64;
+        if remaining_ms <= 0 {
+            warn!("Request deadline {} passed {} ms ago", deadline_ms, -remaining_ms);
+            return Err(Status::GatewayTimeout);
+        }
+        Duration::from_millis(remaining_ms as u64)
+    } else if let Some(value) = request.headers().get_one(GRPC_TIMEOUT_HEADER) {
+        match parse_grpc_timeout(value.trim()) {
+            Some(timeout) if timeout.is_zero() => {
+                warn!("Request arrived with a zero {}", GRPC_TIMEOUT_HEADER);
+                return Err(Status::GatewayTimeout);
+            },
+            Some(timeout) => timeout,
+            None => {
+                warn!("Invalid {} header: {}", GRPC_TIMEOUT_HEADER, value);
+                return Err(Status::BadRequest);
+            },
+        }
+    } else {
+        return Ok(None);
+    };
+
+    debug!("Request deadline in {:?}", remaining);
+    Ok(Some(Instant::now() + remaining))
+}
+
+/// Registry building keys for the effective owner of the request
+/// This is the configured owner unless the admin token sends an `X-Owner-Override` header
+pub struct OwnerRegistry(pub AsyncRegistry);
+
+impl Deref for OwnerRegistry {
+    type Target = AsyncRegistry;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for OwnerRegistry {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let Some(registry) = request.rocket().state::<AsyncRegistry>() else {
+            error!("AsyncRegistry is not managed by Rocket");
+            return Outcome::Error((Status::InternalServerError, ()));
+        };
+
+        if request.rocket().state::<Maintenance>().is_some_and(|maintenance| maintenance.rejects(request)) {
+            debug!("Request rejected: maintenance mode is enabled");
+            return Outcome::Error((Status::ServiceUnavailable, ()));
+        }
+
+        if request.rocket().state::<StartupGate>().is_some_and(|gate| gate.rejects(request)) {
+            debug!("Request rejected: Redis has not been reached yet");
+            return Outcome::Error((Status::ServiceUnavailable, ()));
+        }
+
+        let registry = match request_deadline(request) {
+            Ok(Some(deadline)) => registry.with_deadline(deadline),
+            Ok(None) => registry.clone(),
+            Err(status) => return Outcome::Error((status, ())),
+        };
+
+        let Some(owner) = request.headers().get_one(OWNER_OVERRIDE_HEADER) else {
+            return Outcome::Success(OwnerRegistry(registry));
+        };
+
+        // Only the admin token may act on behalf of other owners
+        match request.guard::<ApiKey>().await {
+            Outcome::Success(api_key) if api_key.is_admin() => {},
+            Outcome::Success(_) => {
+                warn!("{} header rejected for a scoped token", OWNER_OVERRIDE_HEADER);
+                return Outcome::Error((Status::Forbidden, ()));
+            },
+            Outcome::Error((status, _)) => return Outcome::Error((status, ())),
+            Outcome::Forward(status) => return Outcome::Forward(status),
+        }
+
+        match owner.split_once('/') {
+            Some((owner_type, owner_id)) if !owner_type.is_empty() && !owner_id.is_empty() && !owner_id.contains('/') => {
+                info!("Request operating on overridden owner_type={}, owner_id={}", owner_type, owner_id);
+                Outcome::Success(OwnerRegistry(registry.with_owner(owner_type, owner_id)))
+            },
+            _ => {
+                warn!("Invalid {} header: {}", OWNER_OVERRIDE_HEADER, owner);
+                Outcome::Error((Status::BadRequest, ()))
+            },
+        }
+    }
 }
 
 // =======================================================
@@ -26,15 +636,46 @@ pub struct ErrorResponse {
 #[openapi(
     paths(
         set_handler,
+        rotate_handler,
+        swap_handler,
         get_handler,
+        get_with_schema_handler,
+        rate_incr_handler,
+        rate_handler,
         delete_handler,
         purge_handler,
+        purge_where_handler,
         scan_handler,
+        find_handler,
+        match_handler,
         dump_handler,
-        restore_handler
+        get_tree_handler,
+        values_handler,
+        ttls_handler,
+        next_expiry_handler,
+        memory_handler,
+        tree_handler,
+        children_handler,
+        diff_handler,
+        restore_handler,
+        restore_stream_handler,
+        batch_set_handler,
+        batch_set_nx_handler,
+        batch_cas_handler,
+        batch_get_ttl_handler,
+        register_handler,
+        heartbeat_handler,
+        discover_handler,
+        rename_nx_handler,
+        extend_handler,
+        diff_against_handler,
+        expire_handler,
+        persist_handler,
+        type_histogram_handler,
+        pipeline_handler
     ),
     components(
-        schemas(ErrorResponse)
+        schemas(ErrorResponse, PipelineRequestOp, PipelineResponseOp, RotateResponse, BatchSetEntry, BatchSetResponse, BatchSetNxResponse, BatchCasRequestEntry, BatchCasResponse, BatchCasConflictResponse, VersionConflict, ValueWithTtl, NextExpiry, PersistResponse, MemoryUsageResponse, RecursiveDeleteResponse, Registration, RestoreStreamResponse, RestoreChangedResponse, ValueWithSchema, RateCounter, PurgeWhereResponse, ValueMatch, DiffResponse, ChangedValue, DiffAgainstResponse, DriftedValue, TypeHistogramResponse, ChildEntry)
     ),
     tags(
         (name = "registry", description = "Registry API")
@@ -52,33 +693,157 @@ pub struct ApiDoc;
     path = "/registry/set",
     tag = "registry",
     params(
-        ("path" = Option<String>, Query, description = "Key path as a string (can be empty or nested using forward slashes like 'a/b/c')")
+        ("path" = Option<String>, Query, description = "Key path as a string (can be empty or nested using forward slashes like 'a/b/c')"),
+        ("no_content" = Option<bool>, Query, description = "Respond with 204 and an empty body instead of 200 \"OK\" (default: false)"),
+        ("content_type" = Option<String>, Query, description = "Content type returned with the value by /registry/get (default: application/json)"),
+        ("expire_at" = Option<u64>, Query, description = "Unix timestamp in seconds at which the key expires (must be in the future)"),
+        ("if_version" = Option<u64>, Query, description = "Only set the value if the key's version (X-Version of get) equals this, incrementing it"),
+        ("overwrite" = Option<bool>, Query, description = "Replace an existing value; false fails with 409 if the key exists (default: true, false with STRICT_SET)")
     ),
-    request_body = JsonValue,
+    request_body(content = JsonValue, description = "JSON value, or MessagePack with `Content-Type: application/msgpack`; either may be gzip-compressed with `Content-Encoding: gzip`"),
     responses(
         (status = 200, description = "Value successfully set", body = String),
+        (status = 204, description = "Value successfully set (with no_content=true)"),
+        (status = 400, description = "Invalid content type or expiry in the past", body = ErrorResponse),
         (status = 401, description = "Unauthorized", body = ErrorResponse),
-        (status = 500, description = "Internal server error", body = ErrorResponse)
+        (status = 409, description = "The key already exists and overwriting is disabled", body = ErrorResponse),
+        (status = 412, description = "The key's version does not match if_version", body = ErrorResponse),
+        (status = 413, description = "The (decompressed) body exceeds the size limit", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse),
+        (status = 507, description = "The write would exceed the owner's key quota, or Redis is out of memory", body = ErrorResponse)
     )
 )]
-#[post("/set?<path>", format = "json", data = "<value>")]
-pub async fn set_handler(_api_key: ApiKey, registry: &State<AsyncRegistry>, path: Option<String>, value: Json<JsonValue>)
-                         -> Result<status::Custom<String>, status::Custom<Json<ErrorResponse>>> {
-    debug!("Set request received for path: {:?}", path);
-    let span = info_span!("set_handler", path = ?path);
+#[post("/set?<path>&<no_content>&<content_type>&<expire_at>&<if_version>&<overwrite>", data = "<value>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn set_handler(api_key: ApiKey, registry: OwnerRegistry, path: Option<String>, no_content: Option<bool>,
+                         content_type: Option<String>, expire_at: Option<u64>, if_version: Option<u64>, overwrite: Option<bool>,
+                         value: WireValue) -> Result<status::Custom<String>, status::Custom<Json<ErrorResponse>>> {
+    debug!("Set request received for path: {:?}, content type: {:?}, expire at: {:?}, if version: {:?}, overwrite: {:?}", path, content_type, expire_at, if_version, overwrite);
+    let span = info_span!("set_handler", identity = %api_key.identity(), path = ?path, content_type = ?content_type, expire_at = ?expire_at, if_version = ?if_version, overwrite = ?overwrite);
     let _guard = span.enter();
 
+    if let Some(expire_at) = expire_at {
+        if expire_at <= now_ms() / 1000 {
+            warn!("Expiry in the past for path {:?}: {}", path, expire_at);
+            return Err(status::Custom(Status::BadRequest, Json(ErrorResponse::new(format!("Expiry is in the past: {}", expire_at)))));
+        }
+    }
+
+    // Normalize the content type so that get can always parse it back
+    let content_type = match content_type.as_deref().map(str::trim).filter(|c| !c.is_empty()) {
+        Some(raw) => match ContentType::parse_flexible(raw) {
+            Some(parsed) => Some(parsed.to_string()),
+            None => {
+                warn!("Invalid content type for path {:?}: {}", path, raw);
+                return Err(status::Custom(Status::BadRequest, Json(ErrorResponse::new(format!("Invalid content type: {}", raw)))));
+            }
+        },
+        None => None,
+    };
+
     let parts = path_to_parts(&path);
+    validate_parts(&parts)?;
+
+    // An explicit if_version already states which value is replaced, so only plain sets are checked for overwrites
+    // The expiry is written together with the value, so the key never exists without it
+    let overwrite = overwrite.unwrap_or_else(|| !strict_set());
+    let result = match (if_version, expire_at) {
+        (Some(expected), _) => registry.set_if_version(&parts, value.into_inner(), expected, expire_at).await.map(|_| ()),
+        (None, _) if !overwrite => match registry.set_nx(&parts, value.into_inner(), expire_at).await {
+            Ok(false) => {
+                warn!("Set of path {:?} rejected: the key already exists", path);
+                return Err(status::Custom(Status::Conflict, Json(ErrorResponse::new(format!("Key already exists: {}", parts.join(key_separator()))))));
+            },
+            result => result.map(|_| ()),
+        },
+        (None, Some(expire_at)) => registry.set_expiring_at(&parts, value.into_inner(), expire_at).await,
+        (None, None) => registry.set(&parts, value.into_inner()).await,
+    };
+    let result = match result {
+        Ok(_) => registry.set_content_type(&parts, content_type.as_deref()).await,
+        Err(e) => Err(e),
+    };
 
-    match registry.set(&parts, value.into_inner()).await {
+    match result {
         Ok(_) => {
             info!("Value set successfully for path: {:?}", path);
-            Ok(status::Custom(Status::Ok, "OK".to_string()))
+            Ok(mutation_response("OK".to_string(), no_content))
+        },
+        Err(e) => Err(failure_response(&e, format_args!("Failed to set value for path {:?}", path))),
+    }
+}
+
+/// Atomically set a new value and push the previous value onto the key's capped version history
+#[utoipa::path(
+    post,
+    path = "/registry/rotate",
+    tag = "registry",
+    params(
+        ("path" = Option<String>, Query, description = "Key path as a string (can be empty or nested using forward slashes like 'a/b/c')"),
+        ("keep" = usize, Query, description = "Number of previous values to retain in the history")
+    ),
+    request_body(content = JsonValue, description = "JSON value, or MessagePack with `Content-Type: application/msgpack`; either may be gzip-compressed with `Content-Encoding: gzip`"),
+    responses(
+        (status = 200, description = "New value and retained history length", body = RotateResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 409, description = "The key kept changing concurrently", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse),
+        (status = 507, description = "The write would exceed the owner's key quota, or Redis is out of memory", body = ErrorResponse)
+    )
+)]
+#[post("/rotate?<path>&<keep>", data = "<value>")]
+pub async fn rotate_handler(api_key: ApiKey, registry: OwnerRegistry, path: Option<String>, keep: usize, value: WireValue)
+                            -> Result<Json<RotateResponse>, status::Custom<Json<ErrorResponse>>> {
+    debug!("Rotate request received for path: {:?}, keep: {}", path, keep);
+    let span = info_span!("rotate_handler", identity = %api_key.identity(), path = ?path, keep = keep);
+    let _guard = span.enter();
+
+    let parts = path_to_parts(&path);
+    validate_parts(&parts)?;
+    let value = value.into_inner();
+
+    match registry.rotate(&parts, value.clone(), keep).await {
+        Ok(history_length) => {
+            info!("Value rotated for path: {:?}, history length: {}", path, history_length);
+            Ok(Json(RotateResponse { value, history_length }))
         },
-        Err(e) => {
-            error!("Failed to set value for path {:?}: {}", path, e);
-            Err(status::Custom(Status::InternalServerError, Json(ErrorResponse { error: e.to_string() })))
+        Err(e) => Err(failure_response(&e, format_args!("Failed to rotate value for path {:?}", path))),
+    }
+}
+
+/// Set a new value and return the value it replaced, in one atomic step
+#[utoipa::path(
+    post,
+    path = "/registry/swap",
+    tag = "registry",
+    params(
+        ("path" = Option<String>, Query, description = "Key path as a string (can be empty or nested using forward slashes like 'a/b/c')")
+    ),
+    request_body(content = JsonValue, description = "JSON value, or MessagePack with `Content-Type: application/msgpack`; either may be gzip-compressed with `Content-Encoding: gzip`"),
+    responses(
+        (status = 200, description = "Previous value, or null if the key did not exist", body = JsonValue),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 413, description = "The (decompressed) body exceeds the size limit", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse),
+        (status = 507, description = "The write would exceed the owner's key quota, or Redis is out of memory", body = ErrorResponse)
+    )
+)]
+#[post("/swap?<path>", data = "<value>")]
+pub async fn swap_handler(api_key: ApiKey, registry: OwnerRegistry, path: Option<String>, value: WireValue)
+                          -> Result<Json<Option<JsonValue>>, status::Custom<Json<ErrorResponse>>> {
+    debug!("Swap request received for path: {:?}", path);
+    let span = info_span!("swap_handler", identity = %api_key.identity(), path = ?path);
+    let _guard = span.enter();
+
+    let parts = path_to_parts(&path);
+    validate_parts(&parts)?;
+
+    match registry.get_set(&parts, value.into_inner()).await {
+        Ok(previous) => {
+            info!("Value swapped for path: {:?}, previous value found: {}", path, previous.is_some());
+            Ok(Json(previous))
         },
+        Err(e) => Err(failure_response(&e, format_args!("Failed to swap value for path {:?}", path))),
     }
 }
 
@@ -88,253 +853,1854 @@ pub async fn set_handler(_api_key: ApiKey, registry: &State<AsyncRegistry>, path
     path = "/registry/get",
     tag = "registry",
     params(
-        ("path" = Option<String>, Query, description = "Key path as a string (can be empty or nested using forward slashes like 'a/b/c')")
+        ("path" = Option<String>, Query, description = "Key path as a string (can be empty or nested using forward slashes like 'a/b/c')"),
+        ("pretty" = Option<bool>, Query, description = "Pretty-print the JSON response (default: false)"),
+        ("ttl_format" = Option<String>, Query, description = "Report the key's expiry in an X-TTL header: relative (remaining seconds) or absolute (unix timestamp in seconds)"),
+        ("require" = Option<String>, Query, description = "Only return the value if this other key path exists (checked atomically with the read, bypassing the read cache)"),
+        ("max_stale" = Option<u64>, Query, description = "Accept a cached value that expired at most this many seconds ago (default: 0)")
     ),
     responses(
-        (status = 200, description = "JSON value"),
+        (status = 200, description = "Value with the content type it was stored with (JSON unless set with content_type)", headers(
+            ("Content-Type" = String, description = "Content type given when the value was set (default: application/json)"),
+            ("X-TTL" = i64, description = "Expiry in the requested ttl_format (-1: no expiry), only present with ttl_format"),
+            ("X-Version" = u64, description = "Version of the key for set with if_version (0 if it was never written with if_version)"),
+            ("X-Cache" = String, description = "Read cache result (hit, miss or stale), only present when a cache is enabled"),
+            ("X-Cache-Age" = u64, description = "Age of the served value in seconds (0 when read from Redis), only present when a cache is enabled"),
+            ("X-Stale" = String, description = "Set to true when Redis is unreachable and the value was served from the stale cache")
+        )),
+        (status = 400, description = "Invalid ttl_format, or require is mapped to another database than path", body = ErrorResponse),
         (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "The token may not read the required key", body = ErrorResponse),
         (status = 404, description = "Key not found", body = ErrorResponse),
+        (status = 412, description = "The required key does not exist", body = ErrorResponse),
         (status = 500, description = "Internal server error", body = ErrorResponse)
     )
 )]
-#[get("/get?<path>")]
-pub async fn get_handler(_api_key: ApiKey, registry: &State<AsyncRegistry>, path: Option<String>)
-                         -> Result<status::Custom<Json<JsonValue>>, status::Custom<Json<ErrorResponse>>> {
-    debug!("Get request received for path: {:?}", path);
-    let span = info_span!("get_handler", path = ?path);
+#[get("/get?<path>&<pretty>&<ttl_format>&<require>&<max_stale>")]
+pub async fn get_handler(api_key: ApiKey, registry: OwnerRegistry, path: Option<String>, pretty: Option<bool>, ttl_format: Option<String>,
+                         require: Option<String>, max_stale: Option<u64>)
+                         -> Result<WithCacheStatus<status::Custom<StoredValue>>, status::Custom<Json<ErrorResponse>>> {
+    debug!("Get request received for path: {:?}, require: {:?}, max stale: {:?}", path, require, max_stale);
+    let span = info_span!("get_handler", identity = %api_key.identity(), path = ?path, require = ?require, max_stale = ?max_stale);
     let _guard = span.enter();
 
+    let absolute_ttl = ttl_format.as_deref().map(parse_ttl_format).transpose()?;
+
     let parts = path_to_parts(&path);
+    validate_parts(&parts)?;
+
+    let result = match &require {
+        Some(_) => {
+            let require_parts = path_to_parts(&require);
+            validate_parts(&require_parts)?;
+            // Scoped tokens must also be allowed to read the required key
+            if !api_key.permits("get", &require_parts) {
+                warn!("Scoped token {} is not allowed to get path: {:?}", api_key.identity(), require);
+                return Err(status::Custom(Status::Forbidden, Json(ErrorResponse::new(format!("Path not allowed for this token: {}", require_parts.join(key_separator()))))));
+            }
+            registry.get_if_exists(&parts, &require_parts).await.map(|value| (value, None, None))
+        }
+        None => registry.get_max_stale(&parts, Duration::from_secs(max_stale.unwrap_or(0))).await,
+    };
 
-    match registry.get(&parts).await {
-        Ok(Some(value)) => {
-            info!("Value found for path: {:?}", path);
-            Ok(status::Custom(Status::Ok, Json(value)))
+    match result {
+        Ok((Some(value), cache_status, cache_age)) => {
+            info!("Value found for path: {:?} (cache: {:?})", path, cache_status);
+            // Content type, version and TTL are read together, or served from the read cache with the value
+            let metadata = match registry.metadata(&parts, absolute_ttl).await {
+                Ok(metadata) => Some(metadata),
+                Err(e) if absolute_ttl.is_some() => return Err(failure_response(&e, format_args!("Failed to get TTL for path {:?}", path))),
+                Err(e) => {
+                    // The value itself may come from the stale cache during an outage
+                    warn!("Failed to read content type and version for path {:?}, using the defaults: {}", path, e);
+                    None
+                },
+            };
+            let content_type = metadata.as_ref().map_or(DEFAULT_CONTENT_TYPE, |metadata| metadata.content_type.as_str());
+            let body = StoredValue {
+                body: JsonBody { value, pretty: pretty.unwrap_or(false) },
+                content_type: ContentType::parse_flexible(content_type).unwrap_or(ContentType::JSON),
+                ttl: metadata.as_ref().and_then(|metadata| metadata.ttl),
+                version: metadata.as_ref().map(|metadata| metadata.version),
+            };
+            Ok(WithCacheStatus(status::Custom(Status::Ok, body), cache_status, cache_age))
         },
-        Ok(None) => {
+        Ok((None, _, _)) => {
             warn!("Key not found for path: {:?}", path);
-            Err(status::Custom(Status::NotFound, Json(ErrorResponse { error: "Key not found".to_string() })))
-        },
-        Err(e) => {
-            error!("Failed to get value for path {:?}: {}", path, e);
-            Err(status::Custom(Status::InternalServerError, Json(ErrorResponse { error: e.to_string() })))
+            Err(status::Custom(Status::NotFound, Json(ErrorResponse::new("Key not found".to_string()))))
         },
+        Err(e) => Err(failure_response(&e, format_args!("Failed to get value for path {:?}", path))),
     }
 }
 
-/// Delete a key by its path
+/// Get a value together with the JSON Schema registered for its path
 #[utoipa::path(
-    delete,
-    path = "/registry/delete",
+    get,
+    path = "/registry/get-with-schema",
     tag = "registry",
     params(
         ("path" = Option<String>, Query, description = "Key path as a string (can be empty or nested using forward slashes like 'a/b/c')")
     ),
     responses(
-        (status = 200, description = "Key successfully deleted", body = String),
+        (status = 200, description = "Value and the schema registered for the longest prefix of the path (null if none)", body = ValueWithSchema),
         (status = 401, description = "Unauthorized", body = ErrorResponse),
         (status = 404, description = "Key not found", body = ErrorResponse),
         (status = 500, description = "Internal server error", body = ErrorResponse)
     )
 )]
-#[delete("/delete?<path>")]
-pub async fn delete_handler(_api_key: ApiKey, registry: &State<AsyncRegistry>, path: Option<String>)
-                            -> Result<status::Custom<String>, status::Custom<Json<ErrorResponse>>> {
-    debug!("Delete request received for path: {:?}", path);
-    let span = info_span!("delete_handler", path = ?path);
+#[get("/get-with-schema?<path>")]
+pub async fn get_with_schema_handler(api_key: ApiKey, registry: OwnerRegistry, path: Option<String>)
+                                     -> Result<Json<ValueWithSchema>, status::Custom<Json<ErrorResponse>>> {
+    debug!("Get with schema request received for path: {:?}", path);
+    let span = info_span!("get_with_schema_handler", identity = %api_key.identity(), path = ?path);
     let _guard = span.enter();
 
     let parts = path_to_parts(&path);
+    validate_parts(&parts)?;
 
-    match registry.delete(&parts).await {
-        Ok(true) => {
-            info!("Key deleted successfully for path: {:?}", path);
-            Ok(status::Custom(Status::Ok, "OK".to_string()))
-        },
-        Ok(false) => {
-            warn!("Key not found for deletion at path: {:?}", path);
-            Err(status::Custom(Status::NotFound, Json(ErrorResponse { error: "Key not found".to_string() })))
+    let value = match registry.get_with_cache_status(&parts).await {
+        Ok((Some(value), _)) => value,
+        Ok((None, _)) => {
+            warn!("Key not found for path: {:?}", path);
+            return Err(status::Custom(Status::NotFound, Json(ErrorResponse::new("Key not found".to_string()))));
         },
-        Err(e) => {
-            error!("Failed to delete key at path {:?}: {}", path, e);
-            Err(status::Custom(Status::InternalServerError, Json(ErrorResponse { error: e.to_string() })))
+        Err(e) => return Err(failure_response(&e, format_args!("Failed to get value for path {:?}", path))),
+    };
+
+    match registry.schema_for(&parts).await {
+        Ok(schema) => {
+            info!("Retrieved value for path: {:?}, schema registered: {}", path, schema.is_some());
+            Ok(Json(ValueWithSchema { value, schema }))
         },
+        Err(e) => Err(failure_response(&e, format_args!("Failed to get schema for path {:?}", path))),
     }
 }
 
-/// Purge all keys with the specified prefix
+/// Increment a rate counter, starting its window if the counter is new
 #[utoipa::path(
     post,
-    path = "/registry/purge",
+    path = "/registry/rate-incr",
     tag = "registry",
     params(
-        ("path" = Option<String>, Query, description = "Key path prefix as a string (can be empty or nested using forward slashes like 'a/b/c')")
+        ("path" = Option<String>, Query, description = "Key path as a string (can be empty or nested using forward slashes like 'a/b/c')"),
+        ("window" = u64, Query, description = "Window length in seconds, applied when the counter is created")
     ),
     responses(
-        (status = 200, description = "Number of deleted keys", body = String),
+        (status = 200, description = "Count within the current window", body = RateCounter),
+        (status = 400, description = "Window is zero", body = ErrorResponse),
         (status = 401, description = "Unauthorized", body = ErrorResponse),
-        (status = 500, description = "Internal server error", body = ErrorResponse)
+        (status = 500, description = "Internal server error", body = ErrorResponse),
+        (status = 507, description = "The counter would exceed the owner's key quota, or Redis is out of memory", body = ErrorResponse)
     )
 )]
-#[post("/purge?<path>")]
-pub async fn purge_handler(_api_key: ApiKey, registry: &State<AsyncRegistry>, path: Option<String>)
-                           -> Result<status::Custom<String>, status::Custom<Json<ErrorResponse>>> {
-    debug!("Purge request received for path prefix: {:?}", path);
-    let span = info_span!("purge_handler", path = ?path);
+#[post("/rate-incr?<path>&<window>")]
+pub async fn rate_incr_handler(api_key: ApiKey, registry: OwnerRegistry, path: Option<String>, window: u64)
+                               -> Result<Json<RateCounter>, status::Custom<Json<ErrorResponse>>> {
+    debug!("Rate increment request received for path: {:?}, window: {}", path, window);
+    let span = info_span!("rate_incr_handler", identity = %api_key.identity(), path = ?path, window = window);
     let _guard = span.enter();
 
+    if window == 0 {
+        warn!("Rate increment rejected: window is zero");
+        return Err(status::Custom(Status::BadRequest, Json(ErrorResponse::new("window must be at least one second".to_string()))));
+    }
+
     let parts = path_to_parts(&path);
+    validate_parts(&parts)?;
 
-    match registry.purge(&parts).await {
-        Ok(count) => {
-            info!("Purged {} keys with prefix: {:?}", count, path);
-            Ok(status::Custom(Status::Ok, count.to_string()))
-        },
-        Err(e) => {
-            error!("Failed to purge keys with prefix {:?}: {}", path, e);
-            Err(status::Custom(Status::InternalServerError, Json(ErrorResponse { error: e.to_string() })))
+    match registry.rate_incr(&parts, window).await {
+        Ok((count, ttl)) => {
+            debug!("Rate counter for path {:?}: {} ({} seconds left)", path, count, ttl);
+            Ok(Json(RateCounter { count, ttl }))
         },
+        Err(e) => Err(failure_response(&e, format_args!("Failed to increment rate counter for path {:?}", path))),
     }
 }
 
-/// Get list of keys with the specified prefix
+/// Read a rate counter
 #[utoipa::path(
     get,
-    path = "/registry/scan",
+    path = "/registry/rate",
     tag = "registry",
     params(
-        ("path" = Option<String>, Query, description = "Key path prefix as a string (can be empty or nested using forward slashes like 'a/b/c')")
+        ("path" = Option<String>, Query, description = "Key path as a string (can be empty or nested using forward slashes like 'a/b/c')")
     ),
     responses(
-        (status = 200, description = "List of relative key paths"),
+        (status = 200, description = "Count within the current window (0 when no window is running)", body = RateCounter),
         (status = 401, description = "Unauthorized", body = ErrorResponse),
         (status = 500, description = "Internal server error", body = ErrorResponse)
     )
 )]
-#[get("/scan?<path>")]
-pub async fn scan_handler(_api_key: ApiKey, registry: &State<AsyncRegistry>, path: Option<String>)
-                          -> Result<status::Custom<Json<Vec<String>>>, status::Custom<Json<ErrorResponse>>> {
-    debug!("Scan request received for path prefix: {:?}", path);
-    let span = info_span!("scan_handler", path = ?path);
+#[get("/rate?<path>")]
+pub async fn rate_handler(api_key: ApiKey, registry: OwnerRegistry, path: Option<String>)
+                          -> Result<Json<RateCounter>, status::Custom<Json<ErrorResponse>>> {
+    debug!("Rate request received for path: {:?}", path);
+    let span = info_span!("rate_handler", identity = %api_key.identity(), path = ?path);
     let _guard = span.enter();
 
     let parts = path_to_parts(&path);
+    validate_parts(&parts)?;
 
-    match registry.scan(&parts).await {
-        Ok(keys) => {
-            info!("Found {} keys with prefix: {:?}", keys.len(), path);
-            debug!("Keys found: {:?}", keys);
-            Ok(status::Custom(Status::Ok, Json(keys)))
-        },
-        Err(e) => {
-            error!("Failed to scan keys with prefix {:?}: {}", path, e);
-            Err(status::Custom(Status::InternalServerError, Json(ErrorResponse { error: e.to_string() })))
+    match registry.rate(&parts).await {
+        Ok((count, ttl)) => {
+            debug!("Rate counter for path {:?}: {} ({} seconds left)", path, count, ttl);
+            Ok(Json(RateCounter { count, ttl }))
         },
+        Err(e) => Err(failure_response(&e, format_args!("Failed to read rate counter for path {:?}", path))),
     }
 }
 
-/// Dump all keys and values with the specified prefix
+/// Delete a key by its path, optionally with every key below it
 #[utoipa::path(
-    get,
-    path = "/registry/dump",
+    delete,
+    path = "/registry/delete",
     tag = "registry",
     params(
-        ("path" = Option<String>, Query, description = "Key path prefix as a string (can be empty or nested using forward slashes like 'a/b/c')")
+        ("path" = Option<String>, Query, description = "Key path as a string (can be empty or nested using forward slashes like 'a/b/c')"),
+        ("no_content" = Option<bool>, Query, description = "Respond with 204 and an empty body instead of 200 \"OK\" (default: false)"),
+        ("recursive" = Option<bool>, Query, description = "Also delete every key below the path, responding with a breakdown of the deleted keys (default: false)"),
+        ("confirm_root" = Option<bool>, Query, description = "Allow a recursive delete with an empty path (the whole owner keyspace) when ALLOW_ROOT_SCAN is not enabled")
     ),
     responses(
-        (status = 200, description = "JSON object with relative keys and values"),
+        (status = 200, description = "Key successfully deleted (\"OK\", or the breakdown with recursive=true)", body = RecursiveDeleteResponse),
+        (status = 204, description = "Key successfully deleted (with no_content=true)"),
+        (status = 400, description = "Root operation disabled", body = ErrorResponse),
         (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 404, description = "Key not found (nor any key below it with recursive=true)", body = ErrorResponse),
+        (status = 409, description = "Another bulk operation on an overlapping prefix is in progress", body = ErrorResponse),
         (status = 500, description = "Internal server error", body = ErrorResponse)
     )
 )]
-#[get("/dump?<path>")]
-pub async fn dump_handler(_api_key: ApiKey, registry: &State<AsyncRegistry>, path: Option<String>)
-                          -> Result<status::Custom<Json<JsonValue>>, status::Custom<Json<ErrorResponse>>> {
-    debug!("Dump request received for path prefix: {:?}", path);
-    let span = info_span!("dump_handler", path = ?path);
+#[delete("/delete?<path>&<no_content>&<recursive>&<confirm_root>")]
+pub async fn delete_handler(api_key: ApiKey, registry: OwnerRegistry, path: Option<String>, no_content: Option<bool>, recursive: Option<bool>,
+                            confirm_root: Option<bool>) -> Result<DeleteOutcome, status::Custom<Json<ErrorResponse>>> {
+    debug!("Delete request received for path: {:?}, recursive: {:?}", path, recursive);
+    let span = info_span!("delete_handler", identity = %api_key.identity(), path = ?path, recursive = ?recursive);
     let _guard = span.enter();
 
     let parts = path_to_parts(&path);
+    validate_parts(&parts)?;
 
-    match registry.dump(&parts).await {
-        Ok(data) => {
-            let count = match &data {
-                JsonValue::Object(map) => map.len(),
-                _ => 0,
-            };
-            info!("Dumped {} keys with prefix: {:?}", count, path);
-            Ok(status::Custom(Status::Ok, Json(data)))
+    if !recursive.unwrap_or(false) {
+        return match registry.delete(&parts).await {
+            Ok(true) => {
+                info!("Key deleted successfully for path: {:?}", path);
+                Ok(DeleteOutcome::Plain(mutation_response("OK".to_string(), no_content)))
+            },
+            Ok(false) => {
+                warn!("Key not found for deletion at path: {:?}", path);
+                Err(status::Custom(Status::NotFound, Json(ErrorResponse::new("Key not found".to_string()))))
+            },
+            Err(e) => Err(failure_response(&e, format_args!("Failed to delete key at path {:?}", path))),
+        };
+    }
+
+    check_root_operation(&parts, confirm_root, "recursive delete")?;
+
+    match registry.delete_recursive(&parts).await {
+        Ok((false, 0)) => {
+            warn!("Nothing found for recursive deletion at path: {:?}", path);
+            Err(status::Custom(Status::NotFound, Json(ErrorResponse::new("Key not found".to_string()))))
         },
-        Err(e) => {
-            error!("Failed to dump keys with prefix {:?}: {}", path, e);
-            Err(status::Custom(Status::InternalServerError, Json(ErrorResponse { error: e.to_string() })))
+        Ok((key_existed, descendants)) => {
+            info!("Recursively deleted path {:?}: key existed: {}, {} descendants", path, key_existed, descendants);
+            if no_content.unwrap_or(false) {
+                return Ok(DeleteOutcome::Plain(mutation_response(String::new(), no_content)));
+            }
+            let deleted = descendants + i64::from(key_existed);
+            Ok(DeleteOutcome::Recursive(RecursiveDeleteResponse { deleted, key_existed, descendants }))
         },
+        Err(e) => Err(failure_response(&e, format_args!("Failed to recursively delete path {:?}", path))),
     }
 }
 
-/// Restore data from JSON dump
+/// Move a value to a new key, only if the destination does not exist yet
 #[utoipa::path(
     post,
-    path = "/registry/restore",
+    path = "/registry/rename-nx",
     tag = "registry",
     params(
-        ("path" = Option<String>, Query, description = "Key path prefix as a string (can be empty or nested using forward slashes like 'a/b/c')")
+        ("path" = Option<String>, Query, description = "Key path of the value to move, e.g. 'users/john'"),
+        ("to" = String, Query, description = "Destination key path, e.g. 'users/johnny'"),
+        ("no_content" = Option<bool>, Query, description = "Respond with 204 and an empty body instead of 200 \"OK\" (default: false)")
     ),
-    request_body = JsonValue,
     responses(
-        (status = 200, description = "Number of restored keys", body = String),
+        (status = 200, description = "Value moved", body = String),
+        (status = 204, description = "Value moved (with no_content=true)"),
+        (status = 400, description = "The keys are mapped to different databases", body = ErrorResponse),
         (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "The destination is not allowed for this token", body = ErrorResponse),
+        (status = 404, description = "Key not found", body = ErrorResponse),
+        (status = 409, description = "The destination already exists", body = ErrorResponse),
         (status = 500, description = "Internal server error", body = ErrorResponse)
     )
 )]
-#[post("/restore?<path>", format = "json", data = "<data>")]
-pub async fn restore_handler(_api_key: ApiKey, registry: &State<AsyncRegistry>, path: Option<String>, data: Json<JsonValue>)
-                             -> Result<status::Custom<String>, status::Custom<Json<ErrorResponse>>> {
-    debug!("Restore request received for path prefix: {:?}", path);
-    let span = info_span!("restore_handler", path = ?path);
+#[post("/rename-nx?<path>&<to>&<no_content>")]
+pub async fn rename_nx_handler(api_key: ApiKey, registry: OwnerRegistry, path: Option<String>, to: String, no_content: Option<bool>)
+                               -> Result<status::Custom<String>, status::Custom<Json<ErrorResponse>>> {
+    debug!("Rename request received for path: {:?}, destination: {}", path, to);
+    let span = info_span!("rename_nx_handler", identity = %api_key.identity(), path = ?path, to = %to);
     let _guard = span.enter();
 
     let parts = path_to_parts(&path);
+    validate_parts(&parts)?;
+    let to_parts = path_to_parts(&Some(to.clone()));
+    validate_parts(&to_parts)?;
+    if !api_key.permits("rename_nx", &to_parts) {
+        warn!("Scoped token {} is not allowed to rename to path: {}", api_key.identity(), to);
+        return Err(status::Custom(Status::Forbidden, Json(ErrorResponse::new(format!("Path not allowed for this token: {}", to)))));
+    }
 
-    match registry.restore(&parts, data.into_inner()).await {
-        Ok(count) => {
-            info!("Restored {} keys with prefix: {:?}", count, path);
-            Ok(status::Custom(Status::Ok, count.to_string()))
+    match registry.rename_nx(&parts, &to_parts).await {
+        Ok(RenameOutcome::Renamed) => {
+            info!("Renamed path {:?} to {}", path, to);
+            Ok(mutation_response("OK".to_string(), no_content))
         },
-        Err(e) => {
-            error!("Failed to restore keys with prefix {:?}: {}", path, e);
-            Err(status::Custom(Status::InternalServerError, Json(ErrorResponse { error: e.to_string() })))
+        Ok(RenameOutcome::SourceMissing) => {
+            warn!("Key not found for rename at path: {:?}", path);
+            Err(status::Custom(Status::NotFound, Json(ErrorResponse::new("Key not found".to_string()))))
         },
-    }
-}
-
-// Helper function to convert path string to parts vector
-fn path_to_parts(path: &Option<String>) -> Vec<String> {
-    match path {
-        Some(p) if !p.trim().is_empty() => {
-            p.split('/')
-                .map(|s| s.trim().to_string())
-                .filter(|s| !s.is_empty())
-                .collect()
+        Ok(RenameOutcome::DestinationExists) => {
+            warn!("Rename of path {:?} rejected: destination {} exists", path, to);
+            Err(status::Custom(Status::Conflict, Json(ErrorResponse::new(format!("Destination already exists: {}", to)))))
         },
-        _ => Vec::new()
+        Err(e) => Err(failure_response(&e, format_args!("Failed to rename path {:?} to {}", path, to))),
     }
 }
 
-// =======================================================
-// Route Definitions
-// =======================================================
-
-// Function to get api routes
-pub fn routes() -> Vec<Route> {
-    routes![
-        set_handler,
-        get_handler,
-        delete_handler,
-        purge_handler,
-        scan_handler,
+/// Extend the TTLs of all keys with the specified prefix, never beyond a maximum lifetime
+#[utoipa::path(
+    post,
+    path = "/registry/extend",
+    tag = "registry",
+    params(
+        ("path" = Option<String>, Query, description = "Key path prefix as a string (can be empty or nested using forward slashes like 'a/b/c')"),
+        ("by" = u64, Query, description = "Seconds added to the remaining TTL of every key"),
+        ("max" = u64, Query, description = "Maximum TTL in seconds; longer TTLs are capped to it"),
+        ("persistent" = Option<u64>, Query, description = "TTL in seconds given to keys without an expiry (capped at max); without it such keys are skipped"),
+        ("no_content" = Option<bool>, Query, description = "Respond with 204 and an empty body instead of 200 with the count (default: false)"),
+        ("confirm_root" = Option<bool>, Query, description = "Allow an empty path (the whole owner keyspace) when ALLOW_ROOT_SCAN is not enabled")
+    ),
+    responses(
+        (status = 200, description = "Number of keys whose TTL was set", body = String),
+        (status = 204, description = "TTLs extended (with no_content=true)"),
+        (status = 400, description = "max or persistent is zero, or root operation disabled", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+#[post("/extend?<path>&<by>&<max>&<persistent>&<no_content>&<confirm_root>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn extend_handler(api_key: ApiKey, registry: OwnerRegistry, path: Option<String>, by: u64, max: u64, persistent: Option<u64>,
+                            no_content: Option<bool>, confirm_root: Option<bool>)
+                            -> Result<status::Custom<String>, status::Custom<Json<ErrorResponse>>> {
+    debug!("Extend request received for path prefix: {:?}, by: {}, max: {}, persistent: {:?}", path, by, max, persistent);
+    let span = info_span!("extend_handler", identity = %api_key.identity(), path = ?path, by = by, max = max);
+    let _guard = span.enter();
+
+    check_ttl(max)?;
+    if let Some(persistent) = persistent {
+        check_ttl(persistent)?;
+    }
+    let parts = path_to_parts(&path);
+    validate_parts(&parts)?;
+    check_root_operation(&parts, confirm_root, "extend")?;
+
+    match registry.extend_ttls(&parts, by, max, persistent).await {
+        Ok(count) => {
+            info!("Extended TTLs of {} keys with prefix: {:?}", count, path);
+            Ok(mutation_response(count.to_string(), no_content))
+        },
+        Err(e) => Err(failure_response(&e, format_args!("Failed to extend TTLs of keys with prefix {:?}", path))),
+    }
+}
+
+/// Purge all keys with the specified prefix
+#[utoipa::path(
+    post,
+    path = "/registry/purge",
+    tag = "registry",
+    params(
+        ("path" = Option<String>, Query, description = "Key path prefix as a string (can be empty or nested using forward slashes like 'a/b/c')"),
+        ("older_than" = Option<u64>, Query, description = "Only delete keys last modified before this time (unix milliseconds)"),
+        ("no_content" = Option<bool>, Query, description = "Respond with 204 and an empty body instead of 200 with the count (default: false)"),
+        ("scan_count" = Option<u64>, Query, description = "COUNT hint of the SCAN iterations, overriding REDIS_SCAN_COUNT for this request"),
+        ("confirm_root" = Option<bool>, Query, description = "Allow an empty path (the whole owner keyspace) when ALLOW_ROOT_SCAN is not enabled")
+    ),
+    responses(
+        (status = 200, description = "Number of deleted keys", body = String),
+        (status = 204, description = "Keys successfully deleted (with no_content=true)"),
+        (status = 400, description = "Root operation disabled or invalid scan_count", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 409, description = "Another bulk operation on an overlapping prefix is in progress", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+#[post("/purge?<path>&<older_than>&<no_content>&<scan_count>&<confirm_root>")]
+pub async fn purge_handler(api_key: ApiKey, registry: OwnerRegistry, path: Option<String>, older_than: Option<u64>, no_content: Option<bool>,
+                           scan_count: Option<u64>, confirm_root: Option<bool>)
+                           -> Result<status::Custom<String>, status::Custom<Json<ErrorResponse>>> {
+    debug!("Purge request received for path prefix: {:?}, older_than: {:?}", path, older_than);
+    let span = info_span!("purge_handler", identity = %api_key.identity(), path = ?path, older_than = ?older_than);
+    let _guard = span.enter();
+
+    let parts = path_to_parts(&path);
+    validate_parts(&parts)?;
+    check_root_operation(&parts, confirm_root, "purge")?;
+    let registry = apply_scan_count(registry, scan_count)?;
+
+    let result = match older_than {
+        Some(cutoff_ms) => registry.purge_older_than(&parts, cutoff_ms).await,
+        None => registry.purge(&parts).await,
+    };
+
+    match result {
+        Ok(count) => {
+            info!("Purged {} keys with prefix: {:?}", count, path);
+            Ok(mutation_response(count.to_string(), no_content))
+        },
+        Err(e) => Err(failure_response(&e, format_args!("Failed to purge keys with prefix {:?}", path))),
+    }
+}
+
+/// Purge the keys with the specified prefix whose value matches a field/value predicate
+#[utoipa::path(
+    post,
+    path = "/registry/purge-where",
+    tag = "registry",
+    params(
+        ("path" = Option<String>, Query, description = "Key path prefix as a string (can be empty or nested using forward slashes like 'a/b/c')"),
+        ("dry_run" = Option<bool>, Query, description = "Only report the matching keys without deleting them (default: false)"),
+        ("confirm_root" = Option<bool>, Query, description = "Allow an empty path (the whole owner keyspace) when ALLOW_ROOT_SCAN is not enabled")
+    ),
+    request_body = ValueMatch,
+    responses(
+        (status = 200, description = "Deleted (or matching) keys", body = PurgeWhereResponse),
+        (status = 400, description = "Root operation disabled", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 409, description = "Another bulk operation on an overlapping prefix is in progress, or keys kept changing concurrently", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+#[post("/purge-where?<path>&<dry_run>&<confirm_root>", format = "json", data = "<filter>")]
+pub async fn purge_where_handler(api_key: ApiKey, registry: OwnerRegistry, path: Option<String>, dry_run: Option<bool>, confirm_root: Option<bool>,
+                                 filter: Json<ValueMatch>)
+                                 -> Result<Json<PurgeWhereResponse>, status::Custom<Json<ErrorResponse>>> {
+    debug!("Conditional purge request received for path prefix: {:?}, filter: {:?}, dry_run: {:?}", path, filter, dry_run);
+    let span = info_span!("purge_where_handler", identity = %api_key.identity(), path = ?path, dry_run = ?dry_run);
+    let _guard = span.enter();
+
+    let parts = path_to_parts(&path);
+    validate_parts(&parts)?;
+    check_root_operation(&parts, confirm_root, "purge-where")?;
+    let dry_run = dry_run.unwrap_or(false);
+
+    match registry.purge_where(&parts, &filter, dry_run).await {
+        Ok(keys) => {
+            info!("Conditional purge with prefix {:?} matched {} keys (dry_run: {})", path, keys.len(), dry_run);
+            Ok(Json(PurgeWhereResponse { count: keys.len(), keys, dry_run }))
+        },
+        Err(e) => Err(failure_response(&e, format_args!("Failed to purge matching keys with prefix {:?}", path))),
+    }
+}
+
+/// Find the keys with the specified prefix whose value matches a field/value predicate
+#[utoipa::path(
+    get,
+    path = "/registry/find",
+    tag = "registry",
+    params(
+        ("path" = Option<String>, Query, description = "Key path prefix as a string (can be empty or nested using forward slashes like 'a/b/c')"),
+        ("field" = Option<String>, Query, description = "Dot-separated path of a field in object values (e.g. 'status' or 'owner.team'); omit to compare the whole value"),
+        ("equals" = String, Query, description = "Value the field must be equal to, as JSON (e.g. 3, true, \"3\"); anything that is not valid JSON is compared as a string"),
+        ("confirm_root" = Option<bool>, Query, description = "Allow an empty path (the whole owner keyspace) when ALLOW_ROOT_SCAN is not enabled")
+    ),
+    responses(
+        (status = 200, description = "Relative key paths of the matching values"),
+        (status = 400, description = "Root operation disabled", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+#[get("/find?<path>&<field>&<equals>&<confirm_root>")]
+pub async fn find_handler(api_key: ApiKey, registry: OwnerRegistry, path: Option<String>, field: Option<String>, equals: String,
+                          confirm_root: Option<bool>) -> Result<Json<Vec<String>>, status::Custom<Json<ErrorResponse>>> {
+    debug!("Find request received for path prefix: {:?}, field: {:?}, equals: {}", path, field, equals);
+    let span = info_span!("find_handler", identity = %api_key.identity(), path = ?path, field = ?field);
+    let _guard = span.enter();
+
+    let parts = path_to_parts(&path);
+    validate_parts(&parts)?;
+    check_root_operation(&parts, confirm_root, "find")?;
+
+    // Query parameters are strings, so bare words such as active are compared as JSON strings
+    let value = serde_json::from_str(&equals).unwrap_or(JsonValue::String(equals));
+    let filter = ValueMatch { field, value };
+
+    match registry.find(&parts, &filter).await {
+        Ok(keys) => {
+            info!("Found {} matching keys with prefix: {:?}", keys.len(), path);
+            Ok(Json(keys))
+        },
+        Err(e) => Err(failure_response(&e, format_args!("Failed to find matching keys with prefix {:?}", path))),
+    }
+}
+
+/// Get list of keys with the specified prefix
+#[utoipa::path(
+    get,
+    path = "/registry/scan",
+    tag = "registry",
+    params(
+        ("path" = Option<String>, Query, description = "Key path prefix as a string (can be empty or nested using forward slashes like 'a/b/c')"),
+        ("confirm_root" = Option<bool>, Query, description = "Allow an empty path (the whole owner keyspace) when ALLOW_ROOT_SCAN is not enabled"),
+        ("sort" = Option<String>, Query, description = "Sort the keys lexicographically: asc or desc (default: Redis SCAN order)"),
+        ("scan_count" = Option<u64>, Query, description = "COUNT hint of the SCAN iterations, overriding REDIS_SCAN_COUNT for this request")
+    ),
+    responses(
+        (status = 200, description = "List of relative key paths"),
+        (status = 400, description = "Root operation disabled, or invalid sort or scan_count", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+#[get("/scan?<path>&<confirm_root>&<sort>&<scan_count>")]
+pub async fn scan_handler(api_key: ApiKey, registry: OwnerRegistry, path: Option<String>, confirm_root: Option<bool>, sort: Option<String>,
+                          scan_count: Option<u64>)
+                          -> Result<status::Custom<Json<Vec<String>>>, status::Custom<Json<ErrorResponse>>> {
+    debug!("Scan request received for path prefix: {:?}, sort: {:?}", path, sort);
+    let span = info_span!("scan_handler", identity = %api_key.identity(), path = ?path, sort = ?sort);
+    let _guard = span.enter();
+
+    let descending = sort.as_deref().map(parse_sort_order).transpose()?;
+    let parts = path_to_parts(&path);
+    validate_parts(&parts)?;
+    check_root_operation(&parts, confirm_root, "scan")?;
+    let registry = apply_scan_count(registry, scan_count)?;
+
+    match registry.scan(&parts).await {
+        Ok(mut keys) => {
+            // SCAN order is unstable, so sorting needs the complete result set
+            match descending {
+                Some(false) => keys.sort_unstable(),
+                Some(true) => keys.sort_unstable_by(|a, b| b.cmp(a)),
+                None => {},
+            }
+            info!("Found {} keys with prefix: {:?}", keys.len(), path);
+            Ok(status::Custom(Status::Ok, Json(keys)))
+        },
+        Err(e) => Err(failure_response(&e, format_args!("Failed to scan keys with prefix {:?}", path))),
+    }
+}
+
+/// Preview the keys matching a glob pattern, e.g. to validate a pattern before purging with it
+#[utoipa::path(
+    get,
+    path = "/registry/match",
+    tag = "registry",
+    params(
+        ("path" = Option<String>, Query, description = "Key path prefix as a string (can be empty or nested using forward slashes like 'a/b/c')"),
+        ("pattern" = String, Query, description = "Redis glob pattern applied to the key paths relative to the prefix (e.g. 'users/*/settings')")
+    ),
+    responses(
+        (status = 200, description = "List of matching relative key paths"),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Only the admin token may match patterns", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+#[get("/match?<path>&<pattern>")]
+pub async fn match_handler(api_key: ApiKey, registry: OwnerRegistry, path: Option<String>, pattern: String)
+                           -> Result<status::Custom<Json<Vec<String>>>, status::Custom<Json<ErrorResponse>>> {
+    debug!("Match request received for path prefix: {:?}, pattern: {}", path, pattern);
+    let span = info_span!("match_handler", identity = %api_key.identity(), path = ?path, pattern = %pattern);
+    let _guard = span.enter();
+
+    if !api_key.is_admin() {
+        warn!("Match request rejected for a scoped token");
+        return Err(status::Custom(Status::Forbidden, Json(ErrorResponse::new("Only the admin token may match patterns".to_string()))));
+    }
+
+    let parts = path_to_parts(&path);
+    validate_parts(&parts)?;
+
+    match registry.match_keys(&parts, &pattern).await {
+        Ok(keys) => {
+            info!("Pattern {} matched {} keys with prefix: {:?}", pattern, keys.len(), path);
+            Ok(status::Custom(Status::Ok, Json(keys)))
+        },
+        Err(e) => Err(failure_response(&e, format_args!("Failed to match keys with prefix {:?} and pattern {}", path, pattern))),
+    }
+}
+
+/// Dump all keys and values with the specified prefix
+#[utoipa::path(
+    get,
+    path = "/registry/dump",
+    tag = "registry",
+    params(
+        ("path" = Option<String>, Query, description = "Key path prefix as a string (can be empty or nested using forward slashes like 'a/b/c')"),
+        ("pretty" = Option<bool>, Query, description = "Pretty-print the JSON response (default: false, ignored with format=ndjson)"),
+        ("format" = Option<String>, Query, description = "Response format: json (a single object) or ndjson (one {\"key\", \"value\"} object per line, streamed in batches and accepted by /registry/restore-stream). Default: json"),
+        ("scan_count" = Option<u64>, Query, description = "COUNT hint of the SCAN iterations, overriding REDIS_SCAN_COUNT for this request"),
+        ("confirm_root" = Option<bool>, Query, description = "Allow an empty path (the whole owner keyspace) when ALLOW_ROOT_SCAN is not enabled")
+    ),
+    responses(
+        (status = 200, description = "JSON object with relative keys and values, or NDJSON lines with format=ndjson"),
+        (status = 400, description = "Root operation disabled, unknown format or invalid scan_count", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+#[get("/dump?<path>&<pretty>&<format>&<scan_count>&<confirm_root>")]
+pub async fn dump_handler(api_key: ApiKey, registry: OwnerRegistry, path: Option<String>, pretty: Option<bool>, format: Option<String>,
+                          scan_count: Option<u64>, confirm_root: Option<bool>) -> Result<DumpOutcome, status::Custom<Json<ErrorResponse>>> {
+    debug!("Dump request received for path prefix: {:?}, format: {:?}", path, format);
+    let span = info_span!("dump_handler", identity = %api_key.identity(), path = ?path);
+    let _guard = span.enter();
+
+    let parts = path_to_parts(&path);
+    validate_parts(&parts)?;
+    check_root_operation(&parts, confirm_root, "dump")?;
+    let registry = apply_scan_count(registry, scan_count)?;
+
+    let ndjson = match format.as_deref() {
+        None | Some("json") => false,
+        Some("ndjson") => true,
+        Some(other) => return Err(status::Custom(Status::BadRequest, Json(ErrorResponse::new(format!("Unknown format: {}", other))))),
+    };
+
+    if ndjson {
+        return match registry.scan(&parts).await {
+            Ok(mut keys) => {
+                info!("Streaming {} keys with prefix: {:?}", keys.len(), path);
+                // The prefix key itself is skipped like any missing key if it holds no value
+                keys.insert(0, String::new());
+                Ok(DumpOutcome::Ndjson(NdjsonDump { registry, parts, keys }))
+            },
+            Err(e) => Err(failure_response(&e, format_args!("Failed to scan keys with prefix {:?}", path))),
+        };
+    }
+
+    match registry.dump(&parts).await {
+        Ok(data) => {
+            let count = match &data {
+                JsonValue::Object(map) => map.len(),
+                _ => 0,
+            };
+            info!("Dumped {} keys with prefix: {:?}", count, path);
+            Ok(DumpOutcome::Json(status::Custom(Status::Ok, JsonBody { value: data, pretty: pretty.unwrap_or(false) })))
+        },
+        Err(e) => Err(failure_response(&e, format_args!("Failed to dump keys with prefix {:?}", path))),
+    }
+}
+
+/// Get all keys with the specified prefix as a nested JSON object
+#[utoipa::path(
+    get,
+    path = "/registry/get-tree",
+    tag = "registry",
+    params(
+        ("path" = Option<String>, Query, description = "Key path prefix as a string (can be empty or nested using forward slashes like 'a/b/c')"),
+        ("pretty" = Option<bool>, Query, description = "Pretty-print the JSON response (default: false)"),
+        ("confirm_root" = Option<bool>, Query, description = "Allow an empty path (the whole owner keyspace) when ALLOW_ROOT_SCAN is not enabled")
+    ),
+    responses(
+        (status = 200, description = "Nested JSON object built by splitting the relative keys on the key separator (KEY_SEPARATOR, default '/'); a value at a path that also has children is kept under the empty key \"\""),
+        (status = 400, description = "Root operation disabled", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+#[get("/get-tree?<path>&<pretty>&<confirm_root>")]
+pub async fn get_tree_handler(api_key: ApiKey, registry: OwnerRegistry, path: Option<String>, pretty: Option<bool>, confirm_root: Option<bool>)
+                              -> Result<status::Custom<JsonBody>, status::Custom<Json<ErrorResponse>>> {
+    debug!("Get tree request received for path prefix: {:?}", path);
+    let span = info_span!("get_tree_handler", identity = %api_key.identity(), path = ?path);
+    let _guard = span.enter();
+
+    let parts = path_to_parts(&path);
+    validate_parts(&parts)?;
+    check_root_operation(&parts, confirm_root, "get-tree")?;
+
+    match registry.get_tree(&parts).await {
+        Ok(tree) => {
+            info!("Built tree with prefix: {:?}", path);
+            Ok(status::Custom(Status::Ok, JsonBody { value: tree, pretty: pretty.unwrap_or(false) }))
+        },
+        Err(e) => Err(failure_response(&e, format_args!("Failed to get tree with prefix {:?}", path))),
+    }
+}
+
+/// Get the values of all keys with the specified prefix as an array (without the keys)
+#[utoipa::path(
+    get,
+    path = "/registry/values",
+    tag = "registry",
+    params(
+        ("path" = Option<String>, Query, description = "Key path prefix as a string (can be empty or nested using forward slashes like 'a/b/c')")
+    ),
+    responses(
+        (status = 200, description = "JSON array of values in scan order"),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+#[get("/values?<path>")]
+pub async fn values_handler(api_key: ApiKey, registry: OwnerRegistry, path: Option<String>)
+                            -> Result<status::Custom<Json<Vec<JsonValue>>>, status::Custom<Json<ErrorResponse>>> {
+    debug!("Values request received for path prefix: {:?}", path);
+    let span = info_span!("values_handler", identity = %api_key.identity(), path = ?path);
+    let _guard = span.enter();
+
+    let parts = path_to_parts(&path);
+    validate_parts(&parts)?;
+
+    match registry.values(&parts).await {
+        Ok(values) => {
+            info!("Found {} values with prefix: {:?}", values.len(), path);
+            Ok(status::Custom(Status::Ok, Json(values)))
+        },
+        Err(e) => Err(failure_response(&e, format_args!("Failed to get values with prefix {:?}", path))),
+    }
+}
+
+/// Get the remaining TTLs of all keys with the specified prefix
+#[utoipa::path(
+    get,
+    path = "/registry/ttls",
+    tag = "registry",
+    params(
+        ("path" = Option<String>, Query, description = "Key path prefix as a string (can be empty or nested using forward slashes like 'a/b/c')"),
+        ("ttl_format" = Option<String>, Query, description = "relative (remaining seconds) or absolute (unix timestamp in seconds, requires Redis 7). Default: relative")
+    ),
+    responses(
+        (status = 200, description = "JSON object mapping relative keys to their TTL (-1: no expiry, -2: key no longer exists)"),
+        (status = 400, description = "Invalid ttl_format", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+#[get("/ttls?<path>&<ttl_format>")]
+pub async fn ttls_handler(api_key: ApiKey, registry: OwnerRegistry, path: Option<String>, ttl_format: Option<String>)
+                          -> Result<status::Custom<Json<JsonValue>>, status::Custom<Json<ErrorResponse>>> {
+    debug!("TTLs request received for path prefix: {:?}, format: {:?}", path, ttl_format);
+    let span = info_span!("ttls_handler", identity = %api_key.identity(), path = ?path, ttl_format = ?ttl_format);
+    let _guard = span.enter();
+
+    let absolute = ttl_format.as_deref().map(parse_ttl_format).transpose()?.unwrap_or(false);
+    let parts = path_to_parts(&path);
+    validate_parts(&parts)?;
+
+    match registry.ttls(&parts, absolute).await {
+        Ok(ttls) => {
+            info!("Found TTLs of {} keys with prefix: {:?}", ttls.len(), path);
+            Ok(status::Custom(Status::Ok, Json(JsonValue::Object(ttls))))
+        },
+        Err(e) => Err(failure_response(&e, format_args!("Failed to get TTLs with prefix {:?}", path))),
+    }
+}
+
+/// Find the key with the specified prefix that expires first
+#[utoipa::path(
+    get,
+    path = "/registry/next-expiry",
+    tag = "registry",
+    params(
+        ("path" = Option<String>, Query, description = "Key path prefix as a string (can be empty or nested using forward slashes like 'a/b/c')"),
+        ("confirm_root" = Option<bool>, Query, description = "Allow an empty path (the whole owner keyspace) when ALLOW_ROOT_SCAN is not enabled")
+    ),
+    responses(
+        (status = 200, description = "Relative key with the smallest remaining TTL, or null if no key expires", body = NextExpiry),
+        (status = 400, description = "Root operation disabled", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+#[get("/next-expiry?<path>&<confirm_root>")]
+pub async fn next_expiry_handler(api_key: ApiKey, registry: OwnerRegistry, path: Option<String>, confirm_root: Option<bool>)
+                                 -> Result<Json<Option<NextExpiry>>, status::Custom<Json<ErrorResponse>>> {
+    debug!("Next expiry request received for path prefix: {:?}", path);
+    let span = info_span!("next_expiry_handler", identity = %api_key.identity(), path = ?path);
+    let _guard = span.enter();
+
+    let parts = path_to_parts(&path);
+    validate_parts(&parts)?;
+    check_root_operation(&parts, confirm_root, "next-expiry")?;
+
+    match registry.next_expiry(&parts).await {
+        Ok(next) => {
+            info!("Next expiring key with prefix {:?}: {:?}", path, next);
+            Ok(Json(next.map(|(key, ttl)| NextExpiry { key, ttl })))
+        },
+        Err(e) => Err(failure_response(&e, format_args!("Failed to find the next expiring key with prefix {:?}", path))),
+    }
+}
+
+/// Report the memory used in Redis by a key, or by a key and every key below it
+#[utoipa::path(
+    get,
+    path = "/registry/memory",
+    tag = "registry",
+    params(
+        ("path" = Option<String>, Query, description = "Key path as a string (can be empty or nested using forward slashes like 'a/b/c')"),
+        ("recursive" = Option<bool>, Query, description = "Sum the memory used by the key and every key below it (default: false)"),
+        ("confirm_root" = Option<bool>, Query, description = "Allow a recursive report with an empty path (the whole owner keyspace) when ALLOW_ROOT_SCAN is not enabled")
+    ),
+    responses(
+        (status = 200, description = "Bytes used and number of keys measured", body = MemoryUsageResponse),
+        (status = 400, description = "Root operation disabled", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 404, description = "Key not found", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+#[get("/memory?<path>&<recursive>&<confirm_root>")]
+pub async fn memory_handler(api_key: ApiKey, registry: OwnerRegistry, path: Option<String>, recursive: Option<bool>, confirm_root: Option<bool>)
+                            -> Result<Json<MemoryUsageResponse>, status::Custom<Json<ErrorResponse>>> {
+    debug!("Memory usage request received for path: {:?}, recursive: {:?}", path, recursive);
+    let span = info_span!("memory_handler", identity = %api_key.identity(), path = ?path, recursive = ?recursive);
+    let _guard = span.enter();
+
+    let parts = path_to_parts(&path);
+    validate_parts(&parts)?;
+
+    if recursive.unwrap_or(false) {
+        check_root_operation(&parts, confirm_root, "recursive memory report")?;
+        return match registry.memory_usage_recursive(&parts).await {
+            Ok((bytes, keys)) => {
+                info!("Keys with prefix {:?} use {} bytes across {} keys", path, bytes, keys);
+                Ok(Json(MemoryUsageResponse { bytes, keys }))
+            },
+            Err(e) => Err(failure_response(&e, format_args!("Failed to get memory usage of keys with prefix {:?}", path))),
+        };
+    }
+
+    match registry.memory_usage(&parts).await {
+        Ok(Some(bytes)) => {
+            info!("Key at path {:?} uses {} bytes", path, bytes);
+            Ok(Json(MemoryUsageResponse { bytes, keys: 1 }))
+        },
+        Ok(None) => {
+            warn!("Key not found for memory usage at path: {:?}", path);
+            Err(status::Custom(Status::NotFound, Json(ErrorResponse::new("Key not found".to_string()))))
+        },
+        Err(e) => Err(failure_response(&e, format_args!("Failed to get memory usage of key at path {:?}", path))),
+    }
+}
+
+/// List the distinct immediate children of a prefix
+#[utoipa::path(
+    get,
+    path = "/registry/children",
+    tag = "registry",
+    params(
+        ("path" = Option<String>, Query, description = "Key path prefix as a string (can be empty or nested using forward slashes like 'a/b/c')"),
+        ("confirm_root" = Option<bool>, Query, description = "Allow an empty path (the whole owner keyspace) when ALLOW_ROOT_SCAN is not enabled")
+    ),
+    responses(
+        (status = 200, description = "Children sorted by name, flagged as leaves (holding a value) and/or having further children", body = [ChildEntry]),
+        (status = 400, description = "Root operation disabled", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+#[get("/children?<path>&<confirm_root>")]
+pub async fn children_handler(api_key: ApiKey, registry: OwnerRegistry, path: Option<String>, confirm_root: Option<bool>)
+                              -> Result<Json<Vec<ChildEntry>>, status::Custom<Json<ErrorResponse>>> {
+    debug!("Children request received for path prefix: {:?}", path);
+    let span = info_span!("children_handler", identity = %api_key.identity(), path = ?path);
+    let _guard = span.enter();
+
+    let parts = path_to_parts(&path);
+    validate_parts(&parts)?;
+    check_root_operation(&parts, confirm_root, "children")?;
+
+    match registry.children(&parts).await {
+        Ok(children) => {
+            info!("Found {} children of prefix: {:?}", children.len(), path);
+            Ok(Json(children
+                .into_iter()
+                .map(|(name, child)| ChildEntry { name, leaf: child.leaf, has_children: child.has_children })
+                .collect()))
+        },
+        Err(e) => Err(failure_response(&e, format_args!("Failed to list children of prefix {:?}", path))),
+    }
+}
+
+/// Compare the keys and values below two prefixes
+#[utoipa::path(
+    get,
+    path = "/registry/diff",
+    tag = "registry",
+    params(
+        ("a" = Option<String>, Query, description = "First (base) key path prefix, e.g. 'staging/config'"),
+        ("b" = Option<String>, Query, description = "Second (target) key path prefix, e.g. 'prod/config'")
+    ),
+    responses(
+        (status = 200, description = "Keys added in b, removed from a and changed between them", body = DiffResponse),
+        (status = 400, description = "A prefix has too many keys to compare", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "A prefix is not allowed for this token", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+#[get("/diff?<a>&<b>")]
+pub async fn diff_handler(api_key: ApiKey, registry: OwnerRegistry, a: Option<String>, b: Option<String>)
+                          -> Result<Json<DiffResponse>, status::Custom<Json<ErrorResponse>>> {
+    debug!("Diff request received for prefixes: {:?} and {:?}", a, b);
+    let span = info_span!("diff_handler", identity = %api_key.identity(), a = ?a, b = ?b);
+    let _guard = span.enter();
+
+    let a_parts = path_to_parts(&a);
+    validate_parts(&a_parts)?;
+    let b_parts = path_to_parts(&b);
+    validate_parts(&b_parts)?;
+    for parts in [&a_parts, &b_parts] {
+        if !api_key.permits("diff", parts) {
+            warn!("Scoped token {} is not allowed to diff path: {:?}", api_key.identity(), parts);
+            return Err(status::Custom(Status::Forbidden, Json(ErrorResponse::new(format!("Path not allowed for this token: {}", parts.join(key_separator()))))));
+        }
+    }
+
+    match registry.diff(&a_parts, &b_parts).await {
+        Ok(diff) => {
+            info!("Diff of {:?} and {:?}: {} added, {} removed, {} changed", a, b, diff.added.len(), diff.removed.len(), diff.changed.len());
+            Ok(Json(DiffResponse {
+                added: diff.added,
+                removed: diff.removed,
+                changed: diff
+                    .changed
+                    .into_iter()
+                    .map(|(key, (old, new))| (key, ChangedValue { old, new }))
+                    .collect(),
+                unchanged: diff.unchanged,
+            }))
+        },
+        Err(e) => Err(failure_response(&e, format_args!("Failed to diff prefixes {:?} and {:?}", a, b))),
+    }
+}
+
+/// Compare the keys and values below a prefix with an expected state, e.g. to detect configuration drift
+#[utoipa::path(
+    post,
+    path = "/registry/diff-against",
+    tag = "registry",
+    params(
+        ("path" = Option<String>, Query, description = "Key path prefix as a string (can be empty or nested using forward slashes like 'a/b/c')"),
+        ("nested" = Option<bool>, Query, description = "The body is a nested object in the get-tree format instead of an object keyed by relative key path (default: false)")
+    ),
+    request_body(content = JsonValue, description = "Expected state: an object keyed by relative key path as returned by dump, or a nested object as returned by get-tree with nested=true; JSON or MessagePack with `Content-Type: application/msgpack`"),
+    responses(
+        (status = 200, description = "Keys missing from the registry, extra in the registry and with different values", body = DiffAgainstResponse),
+        (status = 400, description = "The prefix has too many keys to compare", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+#[post("/diff-against?<path>&<nested>", data = "<expected>")]
+pub async fn diff_against_handler(api_key: ApiKey, registry: OwnerRegistry, path: Option<String>, nested: Option<bool>, expected: WireValue)
+                                  -> Result<Json<DiffAgainstResponse>, status::Custom<Json<ErrorResponse>>> {
+    debug!("Diff-against request received for path prefix: {:?}, nested: {:?}", path, nested);
+    let span = info_span!("diff_against_handler", identity = %api_key.identity(), path = ?path);
+    let _guard = span.enter();
+
+    let parts = path_to_parts(&path);
+    validate_parts(&parts)?;
+    let expected = match expected.into_inner() {
+        tree if nested.unwrap_or(false) => flatten_tree(tree),
+        JsonValue::Object(entries) => entries.into_iter().collect(),
+        // Like in restore, a value that is not an object is expected at the prefix itself
+        value => BTreeMap::from([(String::new(), value)]),
+    };
+
+    match registry.diff_against(&parts, expected).await {
+        Ok(diff) => {
+            info!("Diff of {:?} against expected state: {} missing, {} extra, {} changed", path, diff.added.len(), diff.removed.len(), diff.changed.len());
+            Ok(Json(DiffAgainstResponse {
+                missing: diff.added,
+                extra: diff.removed,
+                changed: diff
+                    .changed
+                    .into_iter()
+                    .map(|(key, (stored, expected))| (key, DriftedValue { stored, expected }))
+                    .collect(),
+                unchanged: diff.unchanged,
+            }))
+        },
+        Err(e) => Err(failure_response(&e, format_args!("Failed to diff prefix {:?} against expected state", path))),
+    }
+}
+
+/// Count keys with the specified prefix grouped by path segment
+#[utoipa::path(
+    get,
+    path = "/registry/tree",
+    tag = "registry",
+    params(
+        ("path" = Option<String>, Query, description = "Key path prefix as a string (can be empty or nested using forward slashes like 'a/b/c')"),
+        ("depth" = Option<usize>, Query, description = "Number of path segments to break the counts down by (default: 1)"),
+        ("scan_count" = Option<u64>, Query, description = "COUNT hint of the SCAN iterations, overriding REDIS_SCAN_COUNT for this request")
+    ),
+    responses(
+        (status = 200, description = "Nested object of key counts: every node has a count and, above the requested depth, children keyed by path segment"),
+        (status = 400, description = "Invalid scan_count", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+#[get("/tree?<path>&<depth>&<scan_count>")]
+pub async fn tree_handler(api_key: ApiKey, registry: OwnerRegistry, path: Option<String>, depth: Option<usize>, scan_count: Option<u64>)
+                          -> Result<status::Custom<Json<JsonValue>>, status::Custom<Json<ErrorResponse>>> {
+    debug!("Tree request received for path prefix: {:?}, depth: {:?}", path, depth);
+    let span = info_span!("tree_handler", identity = %api_key.identity(), path = ?path, depth = ?depth);
+    let _guard = span.enter();
+
+    let parts = path_to_parts(&path);
+    validate_parts(&parts)?;
+    let registry = apply_scan_count(registry, scan_count)?;
+
+    match registry.tree_counts(&parts, depth.unwrap_or(1)).await {
+        Ok(tree) => {
+            info!("Built key count tree with prefix: {:?}", path);
+            Ok(status::Custom(Status::Ok, Json(tree)))
+        },
+        Err(e) => Err(failure_response(&e, format_args!("Failed to count keys with prefix {:?}", path))),
+    }
+}
+
+/// Count the values with the specified prefix by JSON type
+#[utoipa::path(
+    get,
+    path = "/registry/type-histogram",
+    tag = "registry",
+    params(
+        ("path" = Option<String>, Query, description = "Key path prefix as a string (can be empty or nested using forward slashes like 'a/b/c')"),
+        ("confirm_root" = Option<bool>, Query, description = "Allow an empty path (the whole owner keyspace) when ALLOW_ROOT_SCAN is not enabled")
+    ),
+    responses(
+        (status = 200, description = "Number of values per JSON type", body = TypeHistogramResponse),
+        (status = 400, description = "Root operation disabled", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+#[get("/type-histogram?<path>&<confirm_root>")]
+pub async fn type_histogram_handler(api_key: ApiKey, registry: OwnerRegistry, path: Option<String>, confirm_root: Option<bool>)
+                                    -> Result<Json<TypeHistogramResponse>, status::Custom<Json<ErrorResponse>>> {
+    debug!("Type histogram request received for path prefix: {:?}", path);
+    let span = info_span!("type_histogram_handler", identity = %api_key.identity(), path = ?path);
+    let _guard = span.enter();
+
+    let parts = path_to_parts(&path);
+    validate_parts(&parts)?;
+    check_root_operation(&parts, confirm_root, "type-histogram")?;
+
+    match registry.type_histogram(&parts).await {
+        Ok(histogram) => {
+            info!("Counted the types of {} values with prefix: {:?}", histogram.inspected, path);
+            Ok(Json(TypeHistogramResponse {
+                counts: histogram.counts.into_iter().map(|(json_type, count)| (json_type.to_string(), count)).collect(),
+                inspected: histogram.inspected,
+                truncated: histogram.truncated,
+            }))
+        },
+        Err(e) => Err(failure_response(&e, format_args!("Failed to count value types with prefix {:?}", path))),
+    }
+}
+
+/// Restore data from JSON dump
+#[utoipa::path(
+    post,
+    path = "/registry/restore",
+    tag = "registry",
+    params(
+        ("path" = Option<String>, Query, description = "Key path prefix as a string (can be empty or nested using forward slashes like 'a/b/c')"),
+        ("ttl" = Option<u64>, Query, description = "Optional TTL in seconds applied to every restored key"),
+        ("report" = Option<String>, Query, description = "Set to 'changed' to also return the keys that were new or had a different value")
+    ),
+    request_body(content = JsonValue, description = "JSON value, or MessagePack with `Content-Type: application/msgpack`; either may be gzip-compressed with `Content-Encoding: gzip`"),
+    responses(
+        (status = 200, description = "Number of restored keys, or a RestoreChangedResponse with report=changed", body = String),
+        (status = 400, description = "Invalid report, the TTL is zero, or a key contains reserved or control characters", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 409, description = "Another bulk operation on an overlapping prefix is in progress", body = ErrorResponse),
+        (status = 413, description = "The (decompressed) body exceeds the size limit", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse),
+        (status = 507, description = "The write would exceed the owner's key quota, or Redis is out of memory", body = ErrorResponse)
+    )
+)]
+#[post("/restore?<path>&<ttl>&<report>", data = "<data>")]
+pub async fn restore_handler(api_key: ApiKey, registry: OwnerRegistry, path: Option<String>, ttl: Option<u64>, report: Option<String>, data: WireValue)
+                             -> Result<status::Custom<RestoreOutcome>, status::Custom<Json<ErrorResponse>>> {
+    debug!("Restore request received for path prefix: {:?}, ttl: {:?}, report: {:?}", path, ttl, report);
+    let span = info_span!("restore_handler", identity = %api_key.identity(), path = ?path, ttl = ?ttl);
+    let _guard = span.enter();
+
+    let parts = path_to_parts(&path);
+    validate_parts(&parts)?;
+    if let Some(ttl) = ttl {
+        check_ttl(ttl)?;
+    }
+
+    let result = match report.as_deref() {
+        None => registry.restore(&parts, data.into_inner(), ttl).await.map(RestoreOutcome::Count),
+        Some("changed") => registry
+            .restore_changed(&parts, data.into_inner(), ttl)
+            .await
+            .map(|(restored, changed)| RestoreOutcome::Changed(RestoreChangedResponse { restored, changed })),
+        Some(other) => {
+            warn!("Invalid restore report: {}", other);
+            return Err(status::Custom(Status::BadRequest, Json(ErrorResponse::new(format!("Invalid report: {}. Expected 'changed'", other)))));
+        },
+    };
+
+    match result {
+        Ok(outcome) => {
+            match &outcome {
+                RestoreOutcome::Count(count) => info!("Restored {} keys with prefix: {:?}", count, path),
+                RestoreOutcome::Changed(report) => info!("Restored {} keys with prefix: {:?}, {} changed", report.restored, path, report.changed.len()),
+            }
+            Ok(status::Custom(Status::Ok, outcome))
+        },
+        Err(e) => Err(failure_response(&e, format_args!("Failed to restore keys with prefix {:?}", path))),
+    }
+}
+
+/// Restore data from an NDJSON body read line by line, with bounded memory
+#[utoipa::path(
+    post,
+    path = "/registry/restore-stream",
+    tag = "registry",
+    params(
+        ("path" = Option<String>, Query, description = "Key path prefix as a string (can be empty or nested using forward slashes like 'a/b/c')"),
+        ("ttl" = Option<u64>, Query, description = "Optional TTL in seconds applied to every restored key"),
+        ("strict" = Option<bool>, Query, description = "Fail at the first malformed line instead of skipping it (default: false)")
+    ),
+    request_body(content = String, content_type = "application/x-ndjson", description = "One {\"key\": <relative key>, \"value\": <value>} object per line, optionally gzip-compressed with `Content-Encoding: gzip`"),
+    responses(
+        (status = 200, description = "Number of restored keys and skipped lines", body = RestoreStreamResponse),
+        (status = 400, description = "The TTL is zero, or a line is malformed in strict mode (with its number and the keys restored before it)", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 409, description = "Another bulk operation on an overlapping prefix is in progress", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse),
+        (status = 507, description = "The write would exceed the owner's key quota, or Redis is out of memory", body = ErrorResponse)
+    )
+)]
+#[post("/restore-stream?<path>&<ttl>&<strict>", data = "<data>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn restore_stream_handler(api_key: ApiKey, registry: OwnerRegistry, limits: &Limits, encoding: BodyEncoding, path: Option<String>, ttl: Option<u64>, strict: Option<bool>, data: Data<'_>)
+                                    -> Result<Json<RestoreStreamResponse>, status::Custom<Json<ErrorResponse>>> {
+    debug!("Streaming restore request received for path prefix: {:?}, ttl: {:?}, strict: {:?}", path, ttl, strict);
+    let span = info_span!("restore_stream_handler", identity = %api_key.identity(), path = ?path, ttl = ?ttl);
+    let _guard = span.enter();
+
+    let parts = path_to_parts(&path);
+    validate_parts(&parts)?;
+    if let Some(ttl) = ttl {
+        check_ttl(ttl)?;
+    }
+    // The body is never buffered as a whole, so it gets its own (much larger) limit,
+    // which also bounds the decompressed size of gzipped bodies
+    let limit = limits.get("ndjson").unwrap_or(NDJSON_LIMIT);
+    let reader = encoding.open(data, limit).take(limit.as_u64());
+
+    match registry.restore_lines(&parts, reader, ttl, strict.unwrap_or(false)).await {
+        Ok((restored, failed_lines)) => {
+            info!("Restored {} keys with prefix {:?}, {} lines skipped", restored, path, failed_lines.len());
+            Ok(Json(RestoreStreamResponse { restored, failed_lines }))
+        },
+        Err(e) => Err(failure_response(&e, format_args!("Failed to stream restore with prefix {:?}", path))),
+    }
+}
+
+/// Set several keys in one round trip, each with its own optional TTL
+#[utoipa::path(
+    post,
+    path = "/registry/batch-set",
+    tag = "registry",
+    request_body = Vec<BatchSetEntry>,
+    responses(
+        (status = 200, description = "Number of written keys and of those that expire", body = BatchSetResponse),
+        (status = 400, description = "A TTL is zero", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "A path is not allowed for this token", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse),
+        (status = 507, description = "The write would exceed the owner's key quota, or Redis is out of memory", body = ErrorResponse)
+    )
+)]
+#[post("/batch-set", format = "json", data = "<entries>")]
+pub async fn batch_set_handler(api_key: ApiKey, registry: OwnerRegistry, entries: Json<Vec<BatchSetEntry>>)
+                               -> Result<Json<BatchSetResponse>, status::Custom<Json<ErrorResponse>>> {
+    debug!("Batch set request received with {} entries", entries.len());
+    let span = info_span!("batch_set_handler", identity = %api_key.identity(), entries = entries.len());
+    let _guard = span.enter();
+
+    let mut set_entries = Vec::with_capacity(entries.len());
+    for entry in entries.into_inner() {
+        let parts = path_to_parts(&entry.path);
+        validate_parts(&parts)?;
+
+        // Scoped tokens must be allowed to write every path of the batch
+        if !api_key.permits("batch_set", &parts) {
+            warn!("Scoped token {} is not allowed to write path: {:?}", api_key.identity(), entry.path);
+            return Err(status::Custom(Status::Forbidden, Json(ErrorResponse::new(format!("Operation not allowed for this token: {}", parts.join(key_separator()))))));
+        }
+        if let Some(ttl) = entry.ttl {
+            check_ttl(ttl)?;
+        }
+        set_entries.push((parts, entry.value, entry.ttl));
+    }
+
+    match registry.set_many_ttl(&set_entries).await {
+        Ok((written, with_ttl)) => {
+            info!("Batch set of {} keys, {} with a TTL", written, with_ttl);
+            Ok(Json(BatchSetResponse { written, with_ttl }))
+        },
+        Err(e) => Err(failure_response(&e, format_args!("Failed to execute batch set"))),
+    }
+}
+
+/// Write every key of a path→value object only if it does not exist yet
+#[utoipa::path(
+    post,
+    path = "/registry/batch-set-nx",
+    tag = "registry",
+    params(
+        ("path" = Option<String>, Query, description = "Key path prefix as a string (can be empty or nested using forward slashes like 'a/b/c')")
+    ),
+    request_body(content = JsonValue, description = "Object mapping relative key paths to values, as JSON or MessagePack with `Content-Type: application/msgpack`"),
+    responses(
+        (status = 200, description = "Created and skipped relative key paths", body = BatchSetNxResponse),
+        (status = 400, description = "A key contains reserved or control characters", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 413, description = "The (decompressed) body exceeds the size limit", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse),
+        (status = 507, description = "The write would exceed the owner's key quota, or Redis is out of memory", body = ErrorResponse)
+    )
+)]
+#[post("/batch-set-nx?<path>", data = "<data>")]
+pub async fn batch_set_nx_handler(api_key: ApiKey, registry: OwnerRegistry, path: Option<String>, data: WireValue)
+                                  -> Result<Json<BatchSetNxResponse>, status::Custom<Json<ErrorResponse>>> {
+    debug!("Batch set-if-absent request received for path prefix: {:?}", path);
+    let span = info_span!("batch_set_nx_handler", identity = %api_key.identity(), path = ?path);
+    let _guard = span.enter();
+
+    let parts = path_to_parts(&path);
+    validate_parts(&parts)?;
+
+    match registry.set_many_nx(&parts, data.into_inner()).await {
+        Ok((created, skipped)) => {
+            info!("Batch set-if-absent with prefix {:?}: {} created, {} skipped", path, created.len(), skipped.len());
+            Ok(Json(BatchSetNxResponse { created, skipped }))
+        },
+        Err(e) => Err(failure_response(&e, format_args!("Failed to set absent keys with prefix {:?}", path))),
+    }
+}
+
+/// Set several values in one transaction, each only if its key's version matches, writing nothing on any mismatch
+#[utoipa::path(
+    post,
+    path = "/registry/batch-cas",
+    tag = "registry",
+    request_body = Vec<BatchCasRequestEntry>,
+    responses(
+        (status = 200, description = "All versions matched; new version of every key", body = BatchCasResponse),
+        (status = 400, description = "Duplicate paths, or paths on prefixes mapped to different databases", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "A path is not allowed for this token", body = ErrorResponse),
+        (status = 409, description = "The keys kept changing concurrently and the transaction was given up", body = ErrorResponse),
+        (status = 412, description = "Some versions do not match; nothing was written", body = BatchCasConflictResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse),
+        (status = 507, description = "The write would exceed the owner's key quota, or Redis is out of memory", body = ErrorResponse)
+    )
+)]
+#[post("/batch-cas", format = "json", data = "<entries>")]
+pub async fn batch_cas_handler(api_key: ApiKey, registry: OwnerRegistry, entries: Json<Vec<BatchCasRequestEntry>>)
+                               -> Result<Json<BatchCasResponse>, BatchCasFailure> {
+    debug!("Batch compare-and-set request received with {} entries", entries.len());
+    let span = info_span!("batch_cas_handler", identity = %api_key.identity(), entries = entries.len());
+    let _guard = span.enter();
+
+    let mut paths = Vec::with_capacity(entries.len());
+    let mut cas_entries = Vec::with_capacity(entries.len());
+    for entry in entries.into_inner() {
+        let parts = path_to_parts(&entry.path);
+        validate_parts(&parts).map_err(BatchCasFailure::Error)?;
+
+        // Scoped tokens must be allowed to write every path of the batch
+        if !api_key.permits("batch_cas", &parts) {
+            warn!("Scoped token {} is not allowed to write path: {:?}", api_key.identity(), entry.path);
+            return Err(BatchCasFailure::Error(status::Custom(Status::Forbidden, Json(ErrorResponse::new(format!("Operation not allowed for this token: {}", parts.join(key_separator())))))));
+        }
+
+        let path = parts.join(key_separator());
+        if paths.contains(&path) {
+            warn!("Duplicate path in batch compare-and-set: {}", path);
+            return Err(BatchCasFailure::Error(status::Custom(Status::BadRequest, Json(ErrorResponse::new(format!("Duplicate path: {}", path))))));
+        }
+        paths.push(path);
+        cas_entries.push((parts, entry.if_version, entry.value));
+    }
+
+    match registry.batch_cas(&cas_entries).await {
+        Ok(BatchCasResult::Applied(versions)) => {
+            info!("Batch compare-and-set of {} keys applied", versions.len());
+            Ok(Json(BatchCasResponse { versions: paths.into_iter().zip(versions).collect() }))
+        },
+        Ok(BatchCasResult::Conflicts(conflicts)) => {
+            warn!("Batch compare-and-set rejected, {} versions do not match", conflicts.len());
+            let conflicts = conflicts
+                .into_iter()
+                .map(|(index, current)| VersionConflict { path: paths[index].clone(), expected: cas_entries[index].1, current })
+                .collect();
+            Err(BatchCasFailure::Conflicts(BatchCasConflictResponse { error: "Version does not match".to_string(), conflicts }))
+        },
+        Err(e) => Err(BatchCasFailure::Error(failure_response(&e, format_args!("Failed to execute batch compare-and-set")))),
+    }
+}
+
+/// Get the values of several keys with their remaining TTLs in one round trip
+#[utoipa::path(
+    post,
+    path = "/registry/batch-get-ttl",
+    tag = "registry",
+    request_body(content = Vec<String>, description = "Key paths (e.g. [\"sessions/a\", \"sessions/b\"])"),
+    responses(
+        (status = 200, description = "Value and TTL of every path, null for keys that do not exist", body = BTreeMap<String, ValueWithTtl>),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "A path is not allowed for this token", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+#[post("/batch-get-ttl", format = "json", data = "<paths>")]
+pub async fn batch_get_ttl_handler(api_key: ApiKey, registry: OwnerRegistry, paths: Json<Vec<String>>)
+                                   -> Result<Json<BTreeMap<String, Option<ValueWithTtl>>>, status::Custom<Json<ErrorResponse>>> {
+    debug!("Batch get with TTLs request received with {} paths", paths.len());
+    let span = info_span!("batch_get_ttl_handler", identity = %api_key.identity(), paths = paths.len());
+    let _guard = span.enter();
+
+    let mut all_parts = Vec::with_capacity(paths.len());
+    for path in paths.into_inner() {
+        let parts = path_to_parts(&Some(path));
+        validate_parts(&parts)?;
+
+        // Scoped tokens must be allowed to read every path of the batch
+        if !api_key.permits("batch_get_ttl", &parts) {
+            warn!("Scoped token {} is not allowed to read path: {}", api_key.identity(), parts.join(key_separator()));
+            return Err(status::Custom(Status::Forbidden, Json(ErrorResponse::new(format!("Operation not allowed for this token: {}", parts.join(key_separator()))))));
+        }
+        all_parts.push(parts);
+    }
+
+    match registry.get_with_ttl_many(&all_parts).await {
+        Ok(results) => {
+            info!("Read {} of {} keys with their TTLs", results.iter().flatten().count(), results.len());
+            let values = all_parts
+                .iter()
+                .zip(results)
+                .map(|(parts, result)| (parts.join(key_separator()), result.map(|(value, ttl)| ValueWithTtl { value, ttl })))
+                .collect();
+            Ok(Json(values))
+        },
+        Err(e) => Err(failure_response(&e, format_args!("Failed to get values with TTLs"))),
+    }
+}
+
+/// Register a service instance: set a value that expires unless it is refreshed with heartbeats
+#[utoipa::path(
+    post,
+    path = "/registry/register",
+    tag = "registry",
+    params(
+        ("path" = Option<String>, Query, description = "Key path of the registration (e.g. 'services/api/instance-1')"),
+        ("ttl" = u64, Query, description = "Seconds after which the registration expires without a heartbeat"),
+        ("no_content" = Option<bool>, Query, description = "Respond with 204 and an empty body instead of 200 \"OK\" (default: false)")
+    ),
+    request_body(content = JsonValue, description = "Registration value (e.g. the instance's address), as JSON or MessagePack with `Content-Type: application/msgpack`"),
+    responses(
+        (status = 200, description = "Registration stored", body = String),
+        (status = 204, description = "Registration stored (with no_content=true)"),
+        (status = 400, description = "TTL is zero", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse),
+        (status = 507, description = "The write would exceed the owner's key quota, or Redis is out of memory", body = ErrorResponse)
+    )
+)]
+#[post("/register?<path>&<ttl>&<no_content>", data = "<value>")]
+pub async fn register_handler(api_key: ApiKey, registry: OwnerRegistry, path: Option<String>, ttl: u64, no_content: Option<bool>, value: WireValue)
+                              -> Result<status::Custom<String>, status::Custom<Json<ErrorResponse>>> {
+    debug!("Register request received for path: {:?}, ttl: {}", path, ttl);
+    let span = info_span!("register_handler", identity = %api_key.identity(), path = ?path, ttl = ttl);
+    let _guard = span.enter();
+
+    check_ttl(ttl)?;
+    let parts = path_to_parts(&path);
+    validate_parts(&parts)?;
+
+    match registry.set_with_ttl(&parts, value.into_inner(), ttl).await {
+        Ok(()) => {
+            info!("Registered path: {:?} for {} s", path, ttl);
+            Ok(mutation_response("OK".to_string(), no_content))
+        },
+        Err(e) => Err(failure_response(&e, format_args!("Failed to register path {:?}", path))),
+    }
+}
+
+/// Keep a registration alive by resetting its TTL; fails with 404 once it has expired
+#[utoipa::path(
+    post,
+    path = "/registry/heartbeat",
+    tag = "registry",
+    params(
+        ("path" = Option<String>, Query, description = "Key path of the registration"),
+        ("ttl" = u64, Query, description = "Seconds after which the registration expires without another heartbeat"),
+        ("no_content" = Option<bool>, Query, description = "Respond with 204 and an empty body instead of 200 \"OK\" (default: false)")
+    ),
+    responses(
+        (status = 200, description = "TTL refreshed", body = String),
+        (status = 204, description = "TTL refreshed (with no_content=true)"),
+        (status = 400, description = "TTL is zero", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 404, description = "The registration does not exist or has expired", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+#[post("/heartbeat?<path>&<ttl>&<no_content>")]
+pub async fn heartbeat_handler(api_key: ApiKey, registry: OwnerRegistry, path: Option<String>, ttl: u64, no_content: Option<bool>)
+                               -> Result<status::Custom<String>, status::Custom<Json<ErrorResponse>>> {
+    debug!("Heartbeat request received for path: {:?}, ttl: {}", path, ttl);
+    let span = info_span!("heartbeat_handler", identity = %api_key.identity(), path = ?path, ttl = ttl);
+    let _guard = span.enter();
+
+    check_ttl(ttl)?;
+    let parts = path_to_parts(&path);
+    validate_parts(&parts)?;
+
+    match registry.expire(&parts, ttl, None).await {
+        Ok(true) => {
+            debug!("Heartbeat refreshed path: {:?} for {} s", path, ttl);
+            Ok(mutation_response("OK".to_string(), no_content))
+        },
+        Ok(false) => {
+            warn!("Heartbeat for missing or expired registration at path: {:?}", path);
+            Err(status::Custom(Status::NotFound, Json(ErrorResponse::new("Registration not found or expired".to_string()))))
+        },
+        Err(e) => Err(failure_response(&e, format_args!("Failed to refresh registration at path {:?}", path))),
+    }
+}
+
+/// List the live registrations below a prefix with their values and remaining TTLs
+#[utoipa::path(
+    get,
+    path = "/registry/discover",
+    tag = "registry",
+    params(
+        ("path" = Option<String>, Query, description = "Key path prefix as a string (can be empty or nested using forward slashes like 'a/b/c')"),
+        ("confirm_root" = Option<bool>, Query, description = "Allow an empty path (the whole owner keyspace) when ALLOW_ROOT_SCAN is not enabled")
+    ),
+    responses(
+        (status = 200, description = "Registrations keyed by relative key path", body = BTreeMap<String, Registration>),
+        (status = 400, description = "Empty path without confirm_root", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+#[get("/discover?<path>&<confirm_root>")]
+pub async fn discover_handler(api_key: ApiKey, registry: OwnerRegistry, path: Option<String>, confirm_root: Option<bool>)
+                              -> Result<Json<BTreeMap<String, Registration>>, status::Custom<Json<ErrorResponse>>> {
+    debug!("Discover request received for path prefix: {:?}", path);
+    let span = info_span!("discover_handler", identity = %api_key.identity(), path = ?path);
+    let _guard = span.enter();
+
+    let parts = path_to_parts(&path);
+    validate_parts(&parts)?;
+    check_root_operation(&parts, confirm_root, "discover")?;
+
+    match registry.discover(&parts).await {
+        Ok(registrations) => {
+            info!("Discovered {} registrations with prefix: {:?}", registrations.len(), path);
+            Ok(Json(registrations.into_iter().map(|(key, value, ttl)| (key, Registration { value, ttl })).collect()))
+        },
+        Err(e) => Err(failure_response(&e, format_args!("Failed to discover registrations with prefix {:?}", path))),
+    }
+}
+
+/// Set the TTL of an existing key without rewriting its value, optionally only under a condition
+#[utoipa::path(
+    post,
+    path = "/registry/expire",
+    tag = "registry",
+    params(
+        ("path" = Option<String>, Query, description = "Key path as a string (can be empty or nested using forward slashes like 'a/b/c')"),
+        ("ttl" = u64, Query, description = "Seconds after which the key expires"),
+        ("condition" = Option<String>, Query, description = "Only change the TTL if: nx (the key has none), xx (the key has one), gt (it extends the TTL) or lt (it shortens the TTL, or the key has none)"),
+        ("no_content" = Option<bool>, Query, description = "Respond with 204 and an empty body instead of 200 \"OK\" (default: false)")
+    ),
+    responses(
+        (status = 200, description = "TTL set", body = String),
+        (status = 204, description = "TTL set (with no_content=true)"),
+        (status = 400, description = "TTL is zero, or invalid condition", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 404, description = "Key not found", body = ErrorResponse),
+        (status = 412, description = "The condition does not hold; the TTL was not changed", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+#[post("/expire?<path>&<ttl>&<condition>&<no_content>")]
+pub async fn expire_handler(api_key: ApiKey, registry: OwnerRegistry, path: Option<String>, ttl: u64, condition: Option<String>, no_content: Option<bool>)
+                            -> Result<status::Custom<String>, status::Custom<Json<ErrorResponse>>> {
+    debug!("Expire request received for path: {:?}, ttl: {}, condition: {:?}", path, ttl, condition);
+    let span = info_span!("expire_handler", identity = %api_key.identity(), path = ?path, ttl = ttl, condition = ?condition);
+    let _guard = span.enter();
+
+    check_ttl(ttl)?;
+    let condition = condition.as_deref().map(parse_expire_condition).transpose()?;
+    let parts = path_to_parts(&path);
+    validate_parts(&parts)?;
+
+    match registry.expire(&parts, ttl, condition).await {
+        Ok(true) => {
+            info!("Set TTL of path {:?} to {} s", path, ttl);
+            Ok(mutation_response("OK".to_string(), no_content))
+        },
+        // EXPIRE does not tell a missing key from an unmet condition
+        Ok(false) => match (condition, registry.ttl(&parts, false).await) {
+            (Some(condition), Ok(current)) if current != -2 => {
+                debug!("TTL of path {:?} not changed: condition {:?} does not hold for TTL {}", path, condition, current);
+                Err(status::Custom(Status::PreconditionFailed, Json(ErrorResponse::new("TTL condition not met".to_string()))))
+            },
+            (_, Err(e)) => Err(failure_response(&e, format_args!("Failed to read TTL of path {:?}", path))),
+            _ => {
+                warn!("Key not found for expiry at path: {:?}", path);
+                Err(status::Custom(Status::NotFound, Json(ErrorResponse::new("Key not found".to_string()))))
+            },
+        },
+        Err(e) => Err(failure_response(&e, format_args!("Failed to set TTL of path {:?}", path))),
+    }
+}
+
+/// Remove the TTL of an existing key so that it never expires
+#[utoipa::path(
+    post,
+    path = "/registry/persist",
+    tag = "registry",
+    params(
+        ("path" = Option<String>, Query, description = "Key path as a string (can be empty or nested using forward slashes like 'a/b/c')")
+    ),
+    responses(
+        (status = 200, description = "Whether a TTL was removed (false if the key had none)", body = PersistResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 404, description = "Key not found", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+#[post("/persist?<path>")]
+pub async fn persist_handler(api_key: ApiKey, registry: OwnerRegistry, path: Option<String>)
+                             -> Result<Json<PersistResponse>, status::Custom<Json<ErrorResponse>>> {
+    debug!("Persist request received for path: {:?}", path);
+    let span = info_span!("persist_handler", identity = %api_key.identity(), path = ?path);
+    let _guard = span.enter();
+
+    let parts = path_to_parts(&path);
+    validate_parts(&parts)?;
+
+    match registry.persist(&parts).await {
+        Ok(true) => {
+            info!("Removed TTL of path: {:?}", path);
+            Ok(Json(PersistResponse { persisted: true }))
+        },
+        // PERSIST does not tell a missing key from a key without TTL
+        Ok(false) => match registry.ttl(&parts, false).await {
+            Ok(-2) => {
+                warn!("Key not found for persist at path: {:?}", path);
+                Err(status::Custom(Status::NotFound, Json(ErrorResponse::new("Key not found".to_string()))))
+            },
+            Ok(_) => {
+                debug!("Path {:?} has no TTL to remove", path);
+                Ok(Json(PersistResponse { persisted: false }))
+            },
+            Err(e) => Err(failure_response(&e, format_args!("Failed to read TTL of path {:?}", path))),
+        },
+        Err(e) => Err(failure_response(&e, format_args!("Failed to remove TTL of path {:?}", path))),
+    }
+}
+
+/// Execute a list of get/set/delete operations in one non-atomic pipeline
+#[utoipa::path(
+    post,
+    path = "/registry/pipeline",
+    tag = "registry",
+    request_body = Vec<PipelineRequestOp>,
+    responses(
+        (status = 200, description = "Per-operation results in request order", body = Vec<PipelineResponseOp>),
+        (status = 400, description = "Invalid operation, or operations on prefixes mapped to different databases", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "An operation is not allowed for this token", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse),
+        (status = 507, description = "The sets would exceed the owner's key quota, or Redis is out of memory", body = ErrorResponse)
+    )
+)]
+#[post("/pipeline", format = "json", data = "<ops>")]
+pub async fn pipeline_handler(api_key: ApiKey, registry: OwnerRegistry, ops: Json<Vec<PipelineRequestOp>>)
+                              -> Result<status::Custom<Json<Vec<PipelineResponseOp>>>, status::Custom<Json<ErrorResponse>>> {
+    debug!("Pipeline request received with {} operations", ops.len());
+    let span = info_span!("pipeline_handler", identity = %api_key.identity(), ops = ops.len());
+    let _guard = span.enter();
+
+    let mut pipeline_ops = Vec::with_capacity(ops.len());
+    for request_op in ops.into_inner() {
+        let parts = path_to_parts(&request_op.path);
+        validate_parts(&parts)?;
+
+        // Scoped tokens must be allowed to use every operation of the pipeline
+        if !api_key.permits(&request_op.op, &parts) {
+            warn!("Scoped token {} is not allowed to {} path: {:?}", api_key.identity(), request_op.op, request_op.path);
+            return Err(status::Custom(Status::Forbidden, Json(ErrorResponse::new(format!("Operation not allowed for this token: {}", request_op.op)))));
+        }
+
+        let op = match (request_op.op.as_str(), request_op.value) {
+            ("get", _) => PipelineOp::Get(parts),
+            ("set", Some(value)) => PipelineOp::Set(parts, value),
+            ("set", None) => {
+                warn!("Pipeline set operation without value for path: {:?}", request_op.path);
+                return Err(status::Custom(Status::BadRequest, Json(ErrorResponse::new("Missing value for set operation".to_string()))));
+            },
+            ("delete", _) => PipelineOp::Delete(parts),
+            (op, _) => {
+                warn!("Unknown pipeline operation: {}", op);
+                return Err(status::Custom(Status::BadRequest, Json(ErrorResponse::new(format!("Unknown operation: {}", op)))));
+            },
+        };
+        pipeline_ops.push(op);
+    }
+
+    match registry.pipeline(&pipeline_ops).await {
+        Ok(results) => {
+            info!("Pipeline of {} operations executed", results.len());
+            let responses = results
+                .into_iter()
+                .map(|result| match result {
+                    PipelineResult::Value(Some(value)) => PipelineResponseOp { ok: true, result: Some(value), error: None },
+                    PipelineResult::Set | PipelineResult::Deleted(true) => PipelineResponseOp { ok: true, result: Some(JsonValue::from("OK")), error: None },
+                    PipelineResult::Value(None) | PipelineResult::Deleted(false) => PipelineResponseOp { ok: false, result: None, error: Some("Key not found".to_string()) },
+                })
+                .collect();
+            Ok(status::Custom(Status::Ok, Json(responses)))
+        },
+        Err(e) => Err(failure_response(&e, format_args!("Failed to execute pipeline"))),
+    }
+}
+
+// Helper function to build the response of a failed registry operation from its RegistryError category:
+// 504 when the request deadline passed, 507 when Redis is out of memory (so clients back off instead of
+// retrying), 412 when a version or required key does not match, 409 when a prefix is locked or writes
+// keep conflicting, 400 when the request itself is invalid, 500 otherwise. Failures caused by the request
+// or the current state are logged as warnings with their context, failures of Redis itself as errors
+pub(crate) fn failure_response(e: &RedisError, context: std::fmt::Arguments) -> status::Custom<Json<ErrorResponse>> {
+    let error = RegistryError::from(e);
+    match &error {
+        RegistryError::Redis(_) | RegistryError::Serialization(_) => error!("{}: {}", context, e),
+        RegistryError::OutOfMemory(_) => warn!("{}, Redis is out of memory: {}", context, e),
+        _ => warn!("{}: {}", context, e),
+    }
+    error.into()
+}
+
+// Helper function to reject TTLs of zero, which would delete the key instead of expiring it later
+fn check_ttl(ttl: u64) -> Result<(), status::Custom<Json<ErrorResponse>>> {
+    if ttl > 0 {
+        return Ok(());
+    }
+    warn!("Rejected TTL of zero");
+    Err(status::Custom(Status::BadRequest, Json(ErrorResponse::new("TTL must be at least one second".to_string()))))
+}
+
+// Helper function to apply the scan_count query parameter, which overrides REDIS_SCAN_COUNT for a single request
+fn apply_scan_count(registry: OwnerRegistry, scan_count: Option<u64>) -> Result<AsyncRegistry, status::Custom<Json<ErrorResponse>>> {
+    match scan_count {
+        None => Ok(registry.0),
+        Some(0) => {
+            warn!("Rejected SCAN count of zero");
+            Err(status::Custom(Status::BadRequest, Json(ErrorResponse::new("scan_count must be at least 1".to_string()))))
+        },
+        Some(count) => Ok(registry.with_scan_count(count)),
+    }
+}
+
+// Helper function to read STRICT_SET, on every request so that it can be reloaded: plain sets then refuse to overwrite
+fn strict_set() -> bool {
+    settings::var("STRICT_SET")
+        .map(|value| value.eq_ignore_ascii_case("true") || value == "1")
+        .unwrap_or(false)
+}
+
+// Helper function to reject operations on the whole owner keyspace (empty path) unless ALLOW_ROOT_SCAN
+// is enabled or the request confirms it with confirm_root=true
+fn check_root_operation(parts: &[String], confirm_root: Option<bool>, operation: &str) -> Result<(), status::Custom<Json<ErrorResponse>>> {
+    if !parts.is_empty() || confirm_root.unwrap_or(false) {
+        return Ok(());
+    }
+
+    let allowed = settings::var("ALLOW_ROOT_SCAN")
+        .map(|value| value.eq_ignore_ascii_case("true") || value == "1")
+        .unwrap_or(false);
+    if allowed {
+        return Ok(());
+    }
+
+    warn!("Rejected {} of the whole keyspace without confirm_root", operation);
+    Err(status::Custom(Status::BadRequest, Json(ErrorResponse::new("root operation disabled".to_string()))))
+}
+
+// Helper function to parse a ttl_format parameter: true for absolute timestamps, false for remaining seconds
+fn parse_ttl_format(format: &str) -> Result<bool, status::Custom<Json<ErrorResponse>>> {
+    match format {
+        "relative" => Ok(false),
+        "absolute" => Ok(true),
+        other => {
+            warn!("Unknown TTL format: {}", other);
+            Err(status::Custom(Status::BadRequest, Json(ErrorResponse::new(format!("Unknown ttl_format: {}", other)))))
+        }
+    }
+}
+
+// Helper function to parse a sort parameter: true for descending, false for ascending order
+fn parse_sort_order(order: &str) -> Result<bool, status::Custom<Json<ErrorResponse>>> {
+    match order {
+        "asc" => Ok(false),
+        "desc" => Ok(true),
+        other => {
+            warn!("Unknown sort order: {}", other);
+            Err(status::Custom(Status::BadRequest, Json(ErrorResponse::new(format!("Unknown sort: {}", other)))))
+        }
+    }
+}
+
+// Helper function to parse a condition parameter of expire
+fn parse_expire_condition(condition: &str) -> Result<ExpireCondition, status::Custom<Json<ErrorResponse>>> {
+    match condition {
+        "nx" => Ok(ExpireCondition::Nx),
+        "xx" => Ok(ExpireCondition::Xx),
+        "gt" => Ok(ExpireCondition::Gt),
+        "lt" => Ok(ExpireCondition::Lt),
+        other => {
+            warn!("Unknown expire condition: {}", other);
+            Err(status::Custom(Status::BadRequest, Json(ErrorResponse::new(format!("Unknown condition: {}", other)))))
+        }
+    }
+}
+
+// Helper function to build the response of a successful mutation: 200 with the body, or 204 without it
+fn mutation_response(body: String, no_content: Option<bool>) -> status::Custom<String> {
+    if no_content.unwrap_or(false) {
+        status::Custom(Status::NoContent, String::new())
+    } else {
+        status::Custom(Status::Ok, body)
+    }
+}
+
+/// Default maximum number of segments of a key path (MAX_KEY_DEPTH)
+const DEFAULT_MAX_KEY_DEPTH: usize = 64;
+
+/// Default maximum length of a key path segment in bytes (MAX_SEGMENT_LEN)
+const DEFAULT_MAX_SEGMENT_LEN: usize = 512;
+
+// Helper function to read a positive limit from the environment, on every request so that it can be reloaded
+fn limit_from_env(name: &str, default: usize) -> usize {
+    match settings::var(name).ok().map(|s| s.parse::<usize>()) {
+        Some(Ok(limit)) if limit > 0 => limit,
+        Some(_) => {
+            warn!("Invalid {} value. Using default {}", name, default);
+            default
+        },
+        None => default,
+    }
+}
+
+// Helper function to reject key paths deeper than MAX_KEY_DEPTH, segments longer than MAX_SEGMENT_LEN, and segments
+// containing Redis glob metacharacters or control characters, which would otherwise act as wildcards in prefix scans
+// or inject structure into the key namespace
+pub(crate) fn validate_parts(parts: &[String]) -> Result<(), status::Custom<Json<ErrorResponse>>> {
+    let max_depth = limit_from_env("MAX_KEY_DEPTH", DEFAULT_MAX_KEY_DEPTH);
+    if parts.len() > max_depth {
+        warn!("Rejected key path with {} segments, more than the maximum of {}", parts.len(), max_depth);
+        return Err(status::Custom(Status::BadRequest, Json(ErrorResponse::new(format!("Key path has {} segments, more than the maximum of {}", parts.len(), max_depth)))));
+    }
+
+    let max_segment_len = limit_from_env("MAX_SEGMENT_LEN", DEFAULT_MAX_SEGMENT_LEN);
+    if let Some(segment) = parts.iter().find(|segment| segment.len() > max_segment_len) {
+        warn!("Rejected key path segment of {} bytes, more than the maximum of {}", segment.len(), max_segment_len);
+        return Err(status::Custom(Status::BadRequest, Json(ErrorResponse::new(format!("Path segment of {} bytes is longer than the maximum of {}", segment.len(), max_segment_len)))));
+    }
+
+    match parts.iter().find(|segment| segment.contains(|c: char| c.is_control() || RESERVED_KEY_CHARACTERS.contains(c))) {
+        Some(segment) => {
+            warn!("Rejected path segment with reserved characters: {:?}", segment);
+            Err(status::Custom(Status::BadRequest, Json(ErrorResponse::new(format!("Invalid path segment: {:?}", segment)))))
+        },
+        None => Ok(()),
+    }
+}
+
+// Helper function to convert path string to parts vector
+pub(crate) fn path_to_parts(path: &Option<String>) -> Vec<String> {
+    match path {
+        Some(p) if !p.trim().is_empty() => {
+            p.split(key_separator())
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect()
+        },
+        _ => Vec::new()
+    }
+}
+
+// =======================================================
+// Route Definitions
+// =======================================================
+
+// Function to get api routes
+pub fn routes() -> Vec<Route> {
+    routes![
+        set_handler,
+        rotate_handler,
+        swap_handler,
+        get_handler,
+        get_with_schema_handler,
+        rate_incr_handler,
+        rate_handler,
+        delete_handler,
+        purge_handler,
+        purge_where_handler,
+        scan_handler,
+        find_handler,
+        match_handler,
         dump_handler,
-        restore_handler
+        get_tree_handler,
+        values_handler,
+        ttls_handler,
+        next_expiry_handler,
+        memory_handler,
+        tree_handler,
+        children_handler,
+        diff_handler,
+        restore_handler,
+        restore_stream_handler,
+        batch_set_handler,
+        batch_set_nx_handler,
+        batch_cas_handler,
+        batch_get_ttl_handler,
+        register_handler,
+        heartbeat_handler,
+        discover_handler,
+        rename_nx_handler,
+        extend_handler,
+        diff_against_handler,
+        expire_handler,
+        persist_handler,
+        type_histogram_handler,
+        pipeline_handler
     ]
 }
 
@@ -342,4 +2708,456 @@ pub fn routes() -> Vec<Route> {
 pub fn mount_routes(rocket: rocket::Rocket<rocket::Build>) -> rocket::Rocket<rocket::Build> {
     // Regular API routes
     rocket.mount("/registry", routes())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rocket::http::{Header, Method};
+    use rocket::local::asynchronous::{Client, LocalRequest};
+    use crate::redis_registry::{RegistryConfig, StorageBackend};
+    use crate::admin_api::mount_admin_routes;
+    use crate::health_api::mount_health_routes;
+    use crate::maintenance::MaintenanceFairing;
+    use crate::settings::ConfigReloader;
+    use crate::auth::{TokenScope, TokenScopes};
+    use crate::slow_requests::SlowRequests;
+    use redis::IntoConnectionInfo;
+
+    fn parts(path: &str) -> Vec<String> {
+        path_to_parts(&Some(path.to_string()))
+    }
+
+    // Log writer appending every line to a shared buffer
+    #[derive(Clone, Default)]
+    struct LogBuffer(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl std::io::Write for LogBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[get("/registry/owner")]
+    fn owner(registry: OwnerRegistry) -> String {
+        let description = registry.describe();
+        format!("{}/{}", description.owner_type, description.owner_id)
+    }
+
+    #[get("/body?<pretty>")]
+    fn body(pretty: bool) -> JsonBody {
+        JsonBody { value: serde_json::json!({"a": [1, 2], "b": {"c": null}}), pretty }
+    }
+
+    // Local client of the registry routes; requests rejected by the request guards never connect to Redis,
+    // so a REDIS_URL is only needed to build the registry
+    async fn client(rocket: rocket::Rocket<rocket::Build>) -> Client {
+        if std::env::var("REDIS_URL").is_err() {
+            std::env::set_var("REDIS_URL", "redis://127.0.0.1:6379");
+        }
+        let registry = AsyncRegistry::new(&RegistryConfig {
+            owner_type: "test".to_string(),
+            owner_id: format!("{:016x}", rand::random::<u64>()),
+            storage: StorageBackend::String,
+            cache: None,
+            stale_cache: None,
+            cache_pubsub: false,
+        }).unwrap();
+        Client::tracked(mount_routes(rocket.manage(registry))).await.unwrap()
+    }
+
+    // Authenticate a request with AUTH_TOKEN, if one is configured
+    fn authorized(request: LocalRequest<'_>) -> LocalRequest<'_> {
+        match settings::var("AUTH_TOKEN") {
+            Ok(token) => request.header(Header::new("Authorization", format!("Bearer {}", token))),
+            Err(_) => request,
+        }
+    }
+
+    #[test]
+    fn paths_split_into_trimmed_non_empty_segments() {
+        assert_eq!(parts("a/b/c"), vec!["a", "b", "c"]);
+        assert_eq!(parts("/users// john /"), vec!["users", "john"]);
+        assert!(parts("  ").is_empty());
+        assert!(path_to_parts(&None).is_empty());
+    }
+
+    #[test]
+    fn segments_with_glob_or_control_characters_are_rejected() {
+        assert!(validate_parts(&parts("users/john.doe/settings")).is_ok());
+        for path in ["users/*", "users/jo?n", "a/[b]", "a/b\\c", "a/b\tc", "a/\u{0}"] {
+            let rejected = validate_parts(&parts(path)).unwrap_err();
+            assert_eq!(rejected.0, Status::BadRequest, "{:?}", path);
+        }
+    }
+
+    #[test]
+    fn deep_paths_and_long_segments_are_rejected() {
+        let deep = vec!["a".to_string(); DEFAULT_MAX_KEY_DEPTH + 1];
+        assert!(validate_parts(&deep).is_err());
+        assert!(validate_parts(&deep[..DEFAULT_MAX_KEY_DEPTH]).is_ok());
+        assert!(validate_parts(&["a".repeat(DEFAULT_MAX_SEGMENT_LEN + 1)]).is_err());
+    }
+
+    #[test]
+    fn pipeline_set_keeps_an_explicit_null() {
+        let ops: Vec<PipelineRequestOp> = serde_json::from_str(r#"[
+            {"op": "set", "path": "a", "value": null},
+            {"op": "set", "path": "b"},
+            {"op": "get", "path": "a"}
+        ]"#).unwrap();
+        assert_eq!(ops[0].value, Some(JsonValue::Null));
+        assert_eq!(ops[1].value, None);
+        assert_eq!(ops[2].value, None);
+    }
+
+    #[rocket::async_test]
+    async fn passed_deadlines_are_rejected_before_reaching_redis() {
+        let client = client(rocket::build()).await;
+        let passed = (now_ms() - 1000).to_string();
+        let cases = [
+            (Header::new(DEADLINE_HEADER, passed), Status::GatewayTimeout),
+            (Header::new(GRPC_TIMEOUT_HEADER, "0S"), Status::GatewayTimeout),
+            (Header::new(DEADLINE_HEADER, "soon"), Status::BadRequest),
+            (Header::new(GRPC_TIMEOUT_HEADER, "5x"), Status::BadRequest),
+        ];
+        for (header, status) in cases {
+            let response = authorized(client.get("/registry/get?path=a/b")).header(header.clone()).dispatch().await;
+            assert_eq!(response.status(), status, "{}", header);
+        }
+    }
+
+    #[test]
+    fn root_operations_require_confirmation() {
+        assert!(check_root_operation(&parts("a"), None, "scan").is_ok());
+        assert!(check_root_operation(&parts("a"), Some(false), "scan").is_ok());
+        assert!(check_root_operation(&[], Some(true), "scan").is_ok());
+        if settings::var("ALLOW_ROOT_SCAN").is_err() {
+            for confirm_root in [None, Some(false)] {
+                let rejected = check_root_operation(&[], confirm_root, "scan").unwrap_err();
+                assert_eq!(rejected.0, Status::BadRequest);
+                assert_eq!(rejected.1.error, "root operation disabled");
+            }
+        }
+    }
+
+    #[rocket::async_test]
+    async fn maintenance_blocks_registry_routes_but_not_admin_and_health() {
+        let rocket = rocket::build()
+            .manage(ConfigReloader::new())
+            .manage(TokenScopes::from_env())
+            .manage(Maintenance::new())
+            .manage(StartupGate::new(false))
+            .manage(SlowRequests::from_env())
+            .attach(MaintenanceFairing);
+        let client = client(mount_health_routes(mount_admin_routes(rocket))).await;
+
+        let response = authorized(client.post("/admin/maintenance?enabled=true&retry_after=30")).dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+
+        let response = authorized(client.get("/registry/get?path=a/b")).dispatch().await;
+        assert_eq!(response.status(), Status::ServiceUnavailable);
+        assert_eq!(response.headers().get_one("Retry-After"), Some("30"));
+        assert_eq!(response.into_json::<ErrorResponse>().await.unwrap().error, "Service is in maintenance mode");
+
+        // Health checks reach their handler, whether or not Redis is reachable
+        let response = client.get("/health").dispatch().await;
+        assert!(response.headers().get_one("Retry-After").is_none());
+        assert!(!response.into_string().await.unwrap_or_default().contains("maintenance"));
+    }
+
+    #[rocket::async_test]
+    async fn startup_gate_rejects_registry_routes_until_ready() {
+        let client = client(rocket::build().manage(Maintenance::new()).manage(StartupGate::new(true))).await;
+        let response = authorized(client.get("/registry/get?path=a/b")).dispatch().await;
+        assert_eq!(response.status(), Status::ServiceUnavailable);
+
+        // Once ready, requests get past the gate and stop at the deadline check instead
+        client.rocket().state::<StartupGate>().unwrap().mark_ready();
+        let passed = (now_ms() - 1000).to_string();
+        let response = authorized(client.get("/registry/get?path=a/b")).header(Header::new(DEADLINE_HEADER, passed)).dispatch().await;
+        assert_eq!(response.status(), Status::GatewayTimeout);
+    }
+
+    #[rocket::async_test]
+    #[ignore = "requires a Redis server at REDIS_URL"]
+    async fn startup_gate_serves_registry_routes_once_ready() {
+        let client = client(rocket::build().manage(Maintenance::new()).manage(StartupGate::new(true))).await;
+        let response = authorized(client.post("/registry/set?path=a/b")).body("1").dispatch().await;
+        assert_eq!(response.status(), Status::ServiceUnavailable);
+
+        client.rocket().state::<StartupGate>().unwrap().mark_ready();
+        let response = authorized(client.post("/registry/set?path=a/b")).body("1").dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        let response = authorized(client.get("/registry/get?path=a/b")).dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.into_string().await.unwrap(), "1");
+    }
+
+    #[rocket::async_test]
+    async fn pretty_bodies_are_the_same_json_as_compact_ones() {
+        let client = Client::tracked(rocket::build().mount("/", routes![body])).await.unwrap();
+        let compact = client.get("/body?pretty=false").dispatch().await.into_string().await.unwrap();
+        let pretty = client.get("/body?pretty=true").dispatch().await.into_string().await.unwrap();
+
+        assert_ne!(pretty, compact);
+        assert!(pretty.ends_with("}\n"));
+        assert!(!compact.contains('\n'));
+        assert_eq!(serde_json::from_str::<JsonValue>(&pretty).unwrap(), serde_json::from_str::<JsonValue>(&compact).unwrap());
+    }
+
+    #[rocket::async_test]
+    async fn only_the_admin_token_overrides_the_owner() {
+        let scoped = TokenScope { allow: None, deny: Vec::new(), prefix: None, label: None };
+        let scopes = TokenScopes::new([("scoped-token".to_string(), scoped)].into_iter().collect());
+        let client = client(rocket::build().manage(scopes).mount("/", routes![owner])).await;
+
+        let configured = authorized(client.get("/registry/owner")).dispatch().await.into_string().await.unwrap();
+        assert!(configured.starts_with("test/"), "{}", configured);
+        let response = authorized(client.get("/registry/owner")).header(Header::new(OWNER_OVERRIDE_HEADER, "other/1")).dispatch().await;
+        assert_eq!(response.into_string().await.unwrap(), "other/1");
+        for invalid in ["other", "/1", "other/", "other/1/2"] {
+            let response = authorized(client.get("/registry/owner")).header(Header::new(OWNER_OVERRIDE_HEADER, invalid)).dispatch().await;
+            assert_eq!(response.status(), Status::BadRequest, "{}", invalid);
+        }
+
+        // Scoped tokens only exist when authentication is enabled
+        if settings::var("AUTH_TOKEN").is_ok_and(|token| token != "disabled") {
+            let response = client.get("/registry/owner")
+                .header(Header::new("Authorization", "Bearer scoped-token"))
+                .header(Header::new(OWNER_OVERRIDE_HEADER, "other/1"))
+                .dispatch()
+                .await;
+            assert_eq!(response.status(), Status::Forbidden);
+        }
+    }
+
+    #[rocket::async_test]
+    #[ignore = "requires a Redis server at REDIS_URL"]
+    async fn no_content_mutations_answer_204_without_a_body() {
+        let client = client(rocket::build()).await;
+        let mutations = [
+            (Method::Post, "/registry/set?path=a/b"),
+            (Method::Delete, "/registry/delete?path=a/b"),
+            (Method::Post, "/registry/purge?path=a"),
+        ];
+        for (method, uri) in mutations {
+            for no_content in [false, true] {
+                authorized(client.post("/registry/set?path=a/b")).body("1").dispatch().await;
+                let uri = format!("{}&no_content={}", uri, no_content);
+                let response = authorized(client.req(method, &uri).body("1")).dispatch().await;
+                let expected = if no_content { Status::NoContent } else { Status::Ok };
+                assert_eq!(response.status(), expected, "{}", uri);
+                assert_eq!(response.into_string().await.unwrap_or_default().is_empty(), no_content, "{}", uri);
+            }
+        }
+    }
+
+    #[rocket::async_test]
+    async fn handler_spans_record_the_caller_identity() {
+        use tracing_subscriber::prelude::*;
+
+        let logs = LogBuffer::default();
+        let writer = logs.clone();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_subscriber::fmt::layer().with_ansi(false).with_writer(move || writer.clone()));
+        let _default = tracing::subscriber::set_default(subscriber);
+
+        // Rejected before reaching Redis, but inside the handler span
+        let client = client(rocket::build()).await;
+        let response = authorized(client.post("/registry/set?path=a/b&expire_at=1")).body("1").dispatch().await;
+        assert_eq!(response.status(), Status::BadRequest);
+
+        let token = settings::var("AUTH_TOKEN").unwrap_or_else(|_| "disabled".to_string());
+        let identity = ApiKey(token.clone(), None).identity();
+        let logs = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+        assert!(logs.contains(&format!("set_handler{{identity={}", identity)), "{}", logs);
+        if token != "disabled" {
+            assert!(!logs.contains(&token), "{}", logs);
+        }
+    }
+
+    #[rocket::async_test]
+    #[ignore = "requires a Redis server at REDIS_URL"]
+    async fn gets_answer_with_the_stored_content_type() {
+        let client = client(rocket::build()).await;
+        let response = authorized(client.post("/registry/set?path=a/text&content_type=text/plain")).body("\"hello\"").dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        authorized(client.post("/registry/set?path=a/json")).body("\"hello\"").dispatch().await;
+
+        let response = authorized(client.get("/registry/get?path=a/text")).dispatch().await;
+        assert_eq!(response.content_type(), Some(ContentType::Plain));
+        assert_eq!(response.into_string().await.unwrap(), "hello");
+        let response = authorized(client.get("/registry/get?path=a/json")).dispatch().await;
+        assert_eq!(response.content_type(), Some(ContentType::JSON));
+        assert_eq!(response.into_string().await.unwrap(), "\"hello\"");
+
+        let response = authorized(client.post("/registry/set?path=a/text&content_type=not%20a%20type")).body("1").dispatch().await;
+        assert_eq!(response.status(), Status::BadRequest);
+    }
+
+    #[rocket::async_test]
+    #[ignore = "requires a Redis server at REDIS_URL"]
+    async fn gzipped_restores_are_decompressed() {
+        use rocket::tokio::io::AsyncReadExt;
+
+        let mut body = Vec::new();
+        async_compression::tokio::bufread::GzipEncoder::new(&br#"{"b": 1, "c": {"x": true}}"#[..])
+            .read_to_end(&mut body)
+            .await
+            .unwrap();
+        let client = client(rocket::build()).await;
+        let response = authorized(client.post("/registry/restore?path=a"))
+            .header(Header::new("Content-Encoding", "gzip"))
+            .body(body)
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+
+        let response = authorized(client.get("/registry/dump?path=a")).dispatch().await;
+        assert_eq!(response.into_json::<JsonValue>().await, Some(serde_json::json!({"b": 1, "c": {"x": true}})));
+    }
+
+    #[rocket::async_test]
+    #[ignore = "requires a Redis server at REDIS_URL"]
+    async fn registrations_live_while_heartbeats_arrive() {
+        async fn discover(client: &Client) -> serde_json::Value {
+            let response = authorized(client.get("/registry/discover?path=services")).dispatch().await;
+            assert_eq!(response.status(), Status::Ok);
+            response.into_json().await.unwrap()
+        }
+
+        let client = client(rocket::build()).await;
+
+        for (path, ttl) in [("services/api/1", 5), ("services/api/2", 1)] {
+            let uri = format!("/registry/register?path={}&ttl={}", path, ttl);
+            let response = authorized(client.post(uri)).body(r#"{"host":"a"}"#).dispatch().await;
+            assert_eq!(response.status(), Status::Ok);
+        }
+        let registrations = discover(&client).await;
+        assert_eq!(registrations["api/1"]["value"], serde_json::json!({"host": "a"}));
+        assert!((1..=5).contains(&registrations["api/1"]["ttl"].as_i64().unwrap()));
+
+        // A heartbeat extends the registration without touching its value
+        let response = authorized(client.post("/registry/heartbeat?path=services/api/1&ttl=60")).dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        let registrations = discover(&client).await;
+        assert!(registrations["api/1"]["ttl"].as_i64().unwrap() > 5);
+        assert_eq!(registrations["api/1"]["value"], serde_json::json!({"host": "a"}));
+
+        // Once expired, a registration is gone and its heartbeats are refused
+        rocket::tokio::time::sleep(std::time::Duration::from_millis(2100)).await;
+        let response = authorized(client.post("/registry/heartbeat?path=services/api/2&ttl=60")).dispatch().await;
+        assert_eq!(response.status(), Status::NotFound);
+        let registrations = discover(&client).await;
+        assert_eq!(registrations.as_object().unwrap().keys().collect::<Vec<_>>(), vec!["api/1"]);
+    }
+
+    #[test]
+    fn sort_orders_parse_to_descending_flags() {
+        assert!(!parse_sort_order("asc").unwrap());
+        assert!(parse_sort_order("desc").unwrap());
+        assert_eq!(parse_sort_order("sideways").unwrap_err().0, Status::BadRequest);
+    }
+
+    #[rocket::async_test]
+    #[ignore = "requires a Redis server at REDIS_URL"]
+    async fn scans_sort_keys_on_request() {
+        let client = client(rocket::build()).await;
+        for key in ["b", "c/a", "a", "b/b"] {
+            authorized(client.post(format!("/registry/set?path=s/{}", key))).body("1").dispatch().await;
+        }
+
+        for (sort, expected) in [("asc", ["a", "b", "b/b", "c/a"]), ("desc", ["c/a", "b/b", "b", "a"])] {
+            let response = authorized(client.get(format!("/registry/scan?path=s&sort={}", sort))).dispatch().await;
+            assert_eq!(response.status(), Status::Ok);
+            assert_eq!(response.into_json::<Vec<String>>().await.unwrap(), expected);
+        }
+    }
+
+    #[rocket::async_test]
+    #[ignore = "requires a Redis server at REDIS_URL"]
+    async fn rename_nx_never_overwrites() {
+        let client = client(rocket::build()).await;
+        authorized(client.post("/registry/set?path=a/from")).body("1").dispatch().await;
+        authorized(client.post("/registry/set?path=a/taken")).body("2").dispatch().await;
+
+        let response = authorized(client.post("/registry/rename-nx?path=a/from&to=a/taken")).dispatch().await;
+        assert_eq!(response.status(), Status::Conflict);
+        let response = authorized(client.get("/registry/get?path=a/taken")).dispatch().await;
+        assert_eq!(response.into_string().await.unwrap(), "2");
+
+        let response = authorized(client.post("/registry/rename-nx?path=a/from&to=a/to")).dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        let response = authorized(client.get("/registry/get?path=a/to")).dispatch().await;
+        assert_eq!(response.into_string().await.unwrap(), "1");
+
+        // The source is gone after the move
+        let response = authorized(client.post("/registry/rename-nx?path=a/from&to=a/other")).dispatch().await;
+        assert_eq!(response.status(), Status::NotFound);
+    }
+
+    #[rocket::async_test]
+    async fn admin_config_reports_the_owner_without_secrets() {
+        if std::env::var("REDIS_URL").is_err() {
+            std::env::set_var("REDIS_URL", "redis://127.0.0.1:6379");
+        }
+        let owner_id = format!("{:016x}", rand::random::<u64>());
+        let registry = AsyncRegistry::new(&RegistryConfig {
+            owner_type: "test".to_string(),
+            owner_id: owner_id.clone(),
+            storage: StorageBackend::String,
+            cache: None,
+            stale_cache: None,
+            cache_pubsub: false,
+        }).unwrap();
+        let rocket = rocket::build()
+            .manage(registry)
+            .manage(ConfigReloader::new())
+            .manage(TokenScopes::from_env())
+            .manage(Maintenance::new())
+            .manage(StartupGate::new(false))
+            .manage(SlowRequests::from_env());
+        let client = Client::tracked(mount_admin_routes(rocket)).await.unwrap();
+
+        let response = authorized(client.get("/admin/config")).dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        let body = response.into_string().await.unwrap();
+        let config: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(config["registry"]["owner_type"], "test");
+        assert_eq!(config["registry"]["owner_id"], owner_id.as_str());
+
+        let secrets = ["REDIS_PASSWORD", "VALUE_ENCRYPTION_KEY"]
+            .into_iter()
+            .filter_map(|name| std::env::var(name).ok())
+            .chain(settings::var("AUTH_TOKEN").ok())
+            .chain(std::env::var("REDIS_URL").unwrap().into_connection_info().ok().and_then(|info| info.redis.password));
+        for secret in secrets.filter(|secret| !secret.is_empty()) {
+            assert!(!body.contains(&secret), "secret leaked into /admin/config");
+        }
+    }
+
+    #[rocket::async_test]
+    #[ignore = "requires a Redis server at REDIS_URL"]
+    async fn strict_sets_refuse_overwrites_unless_asked() {
+        // STRICT_SET is process-wide, so the strict behavior is requested per call to leave other tests alone
+        let client = client(rocket::build()).await;
+        let response = authorized(client.post("/registry/set?path=a/b&overwrite=false")).body("1").dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+
+        let response = authorized(client.post("/registry/set?path=a/b&overwrite=false")).body("2").dispatch().await;
+        assert_eq!(response.status(), Status::Conflict);
+        let response = authorized(client.get("/registry/get?path=a/b")).dispatch().await;
+        assert_eq!(response.into_string().await.unwrap(), "1");
+
+        let response = authorized(client.post("/registry/set?path=a/b&overwrite=true")).body("3").dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        let response = authorized(client.get("/registry/get?path=a/b")).dispatch().await;
+        assert_eq!(response.into_string().await.unwrap(), "3");
+    }
+}
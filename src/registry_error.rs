@@ -0,0 +1,187 @@
+// registry_error.rs
+use redis::{ErrorKind, RedisError};
+use rocket::http::Status;
+use rocket::response::{self, status, Responder};
+use rocket::serde::json::Json;
+use rocket::Request;
+
+use crate::redis_registry::{is_contention, is_cross_database, is_deadline_exceeded, is_invalid_key, is_invalid_namespace,
+                            is_malformed_line, is_out_of_memory, is_prefix_locked, is_prefix_too_large, is_quota_exceeded,
+                            is_required_missing, is_version_mismatch};
+use crate::redis_registry_api::ErrorResponse;
+
+// =======================================================
+// Error categories of registry operations
+// =======================================================
+// Registry operations fail with RedisError, carrying our own failures (locks, versions, deadlines, ...) as
+// client errors with a marker prefix. They are classified here once, so that every error response carries
+// the same machine-readable code for the same kind of failure
+
+/// Category of a failed registry operation, mapped to the HTTP status and the code of the error response
+#[derive(Debug, thiserror::Error)]
+pub enum RegistryError {
+    /// A key the operation requires does not exist
+    #[error("{0}")]
+    NotFound(String),
+    /// The operation conflicts with the current state: a prefix lock or concurrent writers
+    #[error("{0}")]
+    Conflict(String),
+    /// A condition of the request does not hold: the key has another version, or a required key does not exist
+    #[error("{0}")]
+    PreconditionFailed(String),
+    /// The request deadline passed before Redis answered
+    #[error("{0}")]
+    Timeout(String),
+    /// A value could not be serialized, deserialized or decoded
+    #[error("{0}")]
+    Serialization(String),
+    /// The write would exceed the owner's key quota (MAX_KEYS_PER_OWNER)
+    #[error("{0}")]
+    QuotaExceeded(String),
+    /// Redis rejected the write because it reached maxmemory
+    #[error("Redis is out of memory, retry later: {0}")]
+    OutOfMemory(String),
+    /// Any other failure of Redis or of the connection to it
+    #[error("{0}")]
+    Redis(String),
+    /// The request is not valid for the keys it touches
+    #[error("{0}")]
+    Validation(String),
+}
+
+impl RegistryError {
+    /// Machine-readable code of the error category, returned in the code field of error responses
+    pub fn code(&self) -> &'static str {
+        match self {
+            RegistryError::NotFound(_) => "not_found",
+            RegistryError::Conflict(_) => "conflict",
+            RegistryError::PreconditionFailed(_) => "precondition_failed",
+            RegistryError::Timeout(_) => "timeout",
+            RegistryError::Serialization(_) => "serialization",
+            RegistryError::QuotaExceeded(_) => "quota_exceeded",
+            RegistryError::OutOfMemory(_) => "out_of_memory",
+            RegistryError::Redis(_) => "redis",
+            RegistryError::Validation(_) => "validation",
+        }
+    }
+
+    /// HTTP status of the error category: 507 when storage is exhausted, so that clients back off instead of retrying
+    pub fn status(&self) -> Status {
+        match self {
+            RegistryError::NotFound(_) => Status::NotFound,
+            RegistryError::Conflict(_) => Status::Conflict,
+            RegistryError::PreconditionFailed(_) => Status::PreconditionFailed,
+            RegistryError::Timeout(_) => Status::GatewayTimeout,
+            RegistryError::Serialization(_) => Status::InternalServerError,
+            RegistryError::QuotaExceeded(_) => Status::InsufficientStorage,
+            RegistryError::OutOfMemory(_) => Status::InsufficientStorage,
+            RegistryError::Redis(_) => Status::InternalServerError,
+            RegistryError::Validation(_) => Status::BadRequest,
+        }
+    }
+}
+
+/// Check whether an error was raised while converting a value rather than by Redis itself
+fn is_serialization_error(e: &RedisError) -> bool {
+    let invalid_data = std::error::Error::source(e)
+        .and_then(|source| source.downcast_ref::<std::io::Error>())
+        .is_some_and(|io| io.kind() == std::io::ErrorKind::InvalidData);
+    invalid_data || e.kind() == ErrorKind::TypeError
+}
+
+impl From<&RedisError> for RegistryError {
+    fn from(e: &RedisError) -> Self {
+        let message = e.to_string();
+        if is_deadline_exceeded(e) {
+            RegistryError::Timeout(message)
+        } else if is_required_missing(e) || is_version_mismatch(e) {
+            RegistryError::PreconditionFailed(message)
+        } else if is_prefix_locked(e) || is_contention(e) {
+            RegistryError::Conflict(message)
+        } else if is_cross_database(e) || is_prefix_too_large(e) || is_invalid_namespace(e) || is_invalid_key(e) || is_malformed_line(e) {
+            RegistryError::Validation(message)
+        } else if is_quota_exceeded(e) {
+            RegistryError::QuotaExceeded(message)
+        } else if is_out_of_memory(e) {
+            RegistryError::OutOfMemory(message)
+        } else if is_serialization_error(e) {
+            RegistryError::Serialization(message)
+        } else {
+            RegistryError::Redis(message)
+        }
+    }
+}
+
+impl From<RedisError> for RegistryError {
+    fn from(e: RedisError) -> Self {
+        RegistryError::from(&e)
+    }
+}
+
+impl From<RegistryError> for status::Custom<Json<ErrorResponse>> {
+    fn from(e: RegistryError) -> Self {
+        status::Custom(e.status(), Json(ErrorResponse::with_code(e.code(), e.to_string())))
+    }
+}
+
+impl<'r> Responder<'r, 'static> for RegistryError {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'static> {
+        status::Custom::<Json<ErrorResponse>>::from(self).respond_to(request)
+    }
+}
+
+/// Code of error responses that were not classified by a RegistryError, derived from their status
+pub fn code_for_status(status: Status) -> &'static str {
+    match status.code {
+        400 => "validation",
+        401 => "unauthorized",
+        403 => "forbidden",
+        404 => "not_found",
+        409 => "conflict",
+        412 => "precondition_failed",
+        413 => "payload_too_large",
+        422 => "validation",
+        429 => "rate_limited",
+        503 => "unavailable",
+        504 => "timeout",
+        507 => "insufficient_storage",
+        _ if status.code >= 500 => "internal",
+        _ => "error",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Error as returned by a Redis server for the given RESP error line
+    fn server_error(line: &str) -> RedisError {
+        redis::parse_redis_value(format!("-{}\r\n", line).as_bytes())
+            .and_then(|value| value.extract_error())
+            .unwrap_err()
+    }
+
+    #[test]
+    fn out_of_memory_is_reported_as_insufficient_storage() {
+        let e = server_error("OOM command not allowed when used memory > 'maxmemory'.");
+        assert!(is_out_of_memory(&e));
+
+        let error = RegistryError::from(&e);
+        assert!(matches!(error, RegistryError::OutOfMemory(_)));
+        assert_eq!(error.status(), Status::InsufficientStorage);
+        assert_eq!(error.code(), "out_of_memory");
+
+        let response = status::Custom::<Json<ErrorResponse>>::from(error);
+        assert_eq!(response.0, Status::InsufficientStorage);
+        assert_eq!(response.1.code.as_deref(), Some("out_of_memory"));
+    }
+
+    #[test]
+    fn other_server_errors_are_redis_failures() {
+        let e = server_error("ERR unknown command 'JSON.SET'");
+        assert!(!is_out_of_memory(&e));
+        let error = RegistryError::from(&e);
+        assert_eq!(error.status(), Status::InternalServerError);
+        assert_eq!(error.code(), "redis");
+    }
+}
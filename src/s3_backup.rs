@@ -0,0 +1,365 @@
+// s3_backup.rs
+use aws_sdk_s3::config::{BehaviorVersion, Credentials, Region};
+use aws_sdk_s3::primitives::ByteStream;
+use aws_sdk_s3::types::{CompletedMultipartUpload, CompletedPart};
+use aws_sdk_s3::Client;
+use rocket::http::Status;
+use rocket::response::status;
+use rocket::serde::json::Json;
+use rocket::{post, routes, Route};
+use serde::Serialize;
+use std::env;
+use utoipa::{OpenApi, ToSchema};
+
+use crate::redis_registry::PIPELINE_BATCH_SIZE;
+use crate::redis_registry_api::{path_to_parts, validate_parts, ErrorResponse, OwnerRegistry};
+use crate::auth::ApiKey;
+
+// =======================================================
+// OpenAPI Documentation
+// =======================================================
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        backup_s3_handler
+    ),
+    components(
+        schemas(S3BackupReport)
+    )
+)]
+pub struct S3BackupApiDoc;
+
+// =======================================================
+// S3-compatible object storage export
+// =======================================================
+
+// Minimum size of every multipart upload part except the last one
+const MIN_PART_SIZE: usize = 5 * 1024 * 1024;
+
+/// Object storage settings, read from the environment on every backup
+struct S3Config {
+    endpoint: Option<String>,
+    region: String,
+    bucket: String,
+    access_key_id: String,
+    secret_access_key: String,
+    key_prefix: String,
+}
+
+impl S3Config {
+    fn from_env() -> Result<Self, String> {
+        let required = |name: &str| env::var(name).map_err(|_| format!("{} is not set", name));
+        Ok(S3Config {
+            endpoint: env::var("S3_ENDPOINT").ok(),
+            region: env::var("S3_REGION").unwrap_or_else(|_| "us-east-1".to_string()),
+            bucket: required("S3_BUCKET")?,
+            access_key_id: required("S3_ACCESS_KEY_ID")?,
+            secret_access_key: required("S3_SECRET_ACCESS_KEY")?,
+            key_prefix: env::var("S3_KEY_PREFIX").unwrap_or_else(|_| "backups/".to_string()),
+        })
+    }
+
+    fn client(&self) -> Client {
+        let credentials = Credentials::new(&self.access_key_id, &self.secret_access_key, None, None, "environment");
+        let mut builder = aws_sdk_s3::Config::builder()
+            .behavior_version(BehaviorVersion::latest())
+            .region(Region::new(self.region.clone()))
+            .credentials_provider(credentials);
+        if let Some(endpoint) = &self.endpoint {
+            // S3-compatible servers such as MinIO usually need path-style addressing
+            builder = builder.endpoint_url(endpoint).force_path_style(true);
+        }
+        Client::from_conf(builder.build())
+    }
+}
+
+/// Uploaded backup object
+#[derive(Debug, Serialize, ToSchema)]
+pub struct S3BackupReport {
+    pub bucket: String,
+    /// Object key of the backup
+    pub key: String,
+    /// Object size in bytes
+    pub size: usize,
+    /// Number of registry keys in the backup
+    pub count: usize,
+}
+
+/// Multipart upload of a backup object, flushing a part whenever enough data is buffered
+struct PartUploader<'a> {
+    client: &'a Client,
+    bucket: &'a str,
+    key: &'a str,
+    upload_id: String,
+    buffer: Vec<u8>,
+    parts: Vec<CompletedPart>,
+    size: usize,
+}
+
+impl PartUploader<'_> {
+    async fn write(&mut self, data: &[u8]) -> Result<(), String> {
+        self.buffer.extend_from_slice(data);
+        self.size += data.len();
+        if self.buffer.len() >= MIN_PART_SIZE {
+            self.flush().await?;
+        }
+        Ok(())
+    }
+
+    async fn flush(&mut self) -> Result<(), String> {
+        let part_number = self.parts.len() as i32 + 1;
+        let body = std::mem::take(&mut self.buffer);
+        debug!("Uploading part {} of {} ({} bytes)", part_number, self.key, body.len());
+
+        let output = self
+            .client
+            .upload_part()
+            .bucket(self.bucket)
+            .key(self.key)
+            .upload_id(&self.upload_id)
+            .part_number(part_number)
+            .body(ByteStream::from(body))
+            .send()
+            .await
+            .map_err(|e| format!("Failed to upload part {}: {}", part_number, e))?;
+
+        self.parts.push(
+            CompletedPart::builder()
+                .part_number(part_number)
+                .set_e_tag(output.e_tag().map(str::to_string))
+                .build(),
+        );
+        Ok(())
+    }
+
+    async fn complete(mut self) -> Result<usize, String> {
+        // The last part may be smaller than the minimum part size, but there must be at least one
+        if !self.buffer.is_empty() || self.parts.is_empty() {
+            self.flush().await?;
+        }
+
+        self.client
+            .complete_multipart_upload()
+            .bucket(self.bucket)
+            .key(self.key)
+            .upload_id(&self.upload_id)
+            .multipart_upload(CompletedMultipartUpload::builder().set_parts(Some(self.parts)).build())
+            .send()
+            .await
+            .map_err(|e| format!("Failed to complete upload: {}", e))?;
+        Ok(self.size)
+    }
+
+    async fn abort(self) {
+        if let Err(e) = self
+            .client
+            .abort_multipart_upload()
+            .bucket(self.bucket)
+            .key(self.key)
+            .upload_id(&self.upload_id)
+            .send()
+            .await
+        {
+            warn!("Failed to abort multipart upload of {}: {}", self.key, e);
+        }
+    }
+}
+
+/// Read the prefix batch by batch and write it to the uploader as a JSON object or as NDJSON lines
+async fn write_backup(registry: &OwnerRegistry, parts: &[String], ndjson: bool, uploader: &mut PartUploader<'_>) -> Result<usize, String> {
+    let keys = registry.scan(parts).await.map_err(|e| e.to_string())?;
+    let mut count = 0;
+
+    if !ndjson {
+        uploader.write(b"{").await?;
+    }
+    for chunk in keys.chunks(PIPELINE_BATCH_SIZE) {
+        let entries = registry.read_entries(parts, chunk.to_vec()).await.map_err(|e| e.to_string())?;
+        let mut data = Vec::new();
+        for (key, value) in entries {
+            let encoded = if ndjson {
+                serde_json::to_string(&serde_json::json!({ "key": key, "value": value })).map(|line| format!("{}\n", line))
+            } else {
+                let separator = if count > 0 { "," } else { "" };
+                serde_json::to_string(&key)
+                    .and_then(|key| serde_json::to_string(&value).map(|value| format!("{}{}:{}", separator, key, value)))
+            };
+            data.extend(encoded.map_err(|e| e.to_string())?.into_bytes());
+            count += 1;
+        }
+        uploader.write(&data).await?;
+    }
+    if !ndjson {
+        uploader.write(b"}").await?;
+    }
+
+    Ok(count)
+}
+
+// =======================================================
+// REST API Handlers
+// =======================================================
+
+/// Dump the keys and values with the specified prefix to an S3-compatible bucket
+#[utoipa::path(
+    post,
+    path = "/admin/backup-s3",
+    tag = "admin",
+    params(
+        ("path" = Option<String>, Query, description = "Key path prefix as a string (can be empty or nested using forward slashes like 'a/b/c')"),
+        ("format" = Option<String>, Query, description = "Object format: json (a single object like /registry/dump) or ndjson (one {\"key\", \"value\"} object per line). Default: json")
+    ),
+    responses(
+        (status = 200, description = "Uploaded backup object", body = S3BackupReport),
+        (status = 400, description = "Invalid format or object storage is not configured", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+#[post("/backup-s3?<path>&<format>")]
+pub async fn backup_s3_handler(api_key: ApiKey, registry: OwnerRegistry, path: Option<String>, format: Option<String>)
+                               -> Result<Json<S3BackupReport>, status::Custom<Json<ErrorResponse>>> {
+    debug!("S3 backup request received for path prefix: {:?}, format: {:?}", path, format);
+    let span = info_span!("backup_s3_handler", identity = %api_key.identity(), path = ?path, format = ?format);
+    let _guard = span.enter();
+
+    let bad_request = |error: String| {
+        warn!("S3 backup rejected: {}", error);
+        status::Custom(Status::BadRequest, Json(ErrorResponse::new(error)))
+    };
+    let internal_error = |error: String| {
+        error!("S3 backup failed: {}", error);
+        status::Custom(Status::InternalServerError, Json(ErrorResponse::new(error)))
+    };
+
+    let ndjson = match format.as_deref() {
+        None | Some("json") => false,
+        Some("ndjson") => true,
+        Some(other) => return Err(bad_request(format!("Unknown format: {}", other))),
+    };
+    let config = S3Config::from_env().map_err(bad_request)?;
+    let client = config.client();
+
+    let parts = path_to_parts(&path);
+    validate_parts(&parts)?;
+    let key = format!(
+        "{}{}-{}.{}",
+        config.key_prefix,
+        if parts.is_empty() { "root".to_string() } else { parts.join("_") },
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_millis())
+            .unwrap_or(0),
+        if ndjson { "ndjson" } else { "json" }
+    );
+    info!("Backing up prefix {:?} to s3://{}/{}", path, config.bucket, key);
+
+    let upload = client
+        .create_multipart_upload()
+        .bucket(&config.bucket)
+        .key(&key)
+        .content_type(if ndjson { "application/x-ndjson" } else { "application/json" })
+        .send()
+        .await
+        .map_err(|e| internal_error(format!("Failed to start upload: {}", e)))?;
+    let upload_id = upload
+        .upload_id()
+        .ok_or_else(|| internal_error("Object storage returned no upload id".to_string()))?
+        .to_string();
+
+    let mut uploader = PartUploader {
+        client: &client,
+        bucket: &config.bucket,
+        key: &key,
+        upload_id,
+        buffer: Vec::new(),
+        parts: Vec::new(),
+        size: 0,
+    };
+
+    let count = match write_backup(&registry, &parts, ndjson, &mut uploader).await {
+        Ok(count) => count,
+        Err(e) => {
+            uploader.abort().await;
+            return Err(internal_error(e));
+        }
+    };
+    let size = uploader.complete().await.map_err(internal_error)?;
+
+    info!("Backed up {} keys ({} bytes) to s3://{}/{}", count, size, config.bucket, key);
+    Ok(Json(S3BackupReport {
+        bucket: config.bucket.clone(),
+        key,
+        size,
+        count,
+    }))
+}
+
+// =======================================================
+// Route Definitions
+// =======================================================
+
+// Function to get S3 backup routes
+pub fn routes() -> Vec<Route> {
+    routes![
+        backup_s3_handler
+    ]
+}
+
+// Function to mount S3 backup routes
+pub fn mount_s3_backup_routes(rocket: rocket::Rocket<rocket::Build>) -> rocket::Rocket<rocket::Build> {
+    rocket.mount("/admin", routes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rocket::local::asynchronous::Client as LocalClient;
+    use crate::redis_registry::{AsyncRegistry, RegistryConfig, StorageBackend};
+
+    #[rocket::async_test]
+    #[ignore = "requires a Redis server at REDIS_URL and an S3-compatible server (e.g. MinIO) configured with S3_*"]
+    async fn backups_are_uploaded_as_json_and_ndjson() {
+        let registry = AsyncRegistry::new(&RegistryConfig {
+            owner_type: "test".to_string(),
+            owner_id: format!("{:016x}", rand::random::<u64>()),
+            storage: StorageBackend::String,
+            cache: None,
+            stale_cache: None,
+            cache_pubsub: false,
+        }).unwrap();
+        let parts = path_to_parts(&Some("a".to_string()));
+        registry.restore(&parts, serde_json::json!({"b": 1, "c": {"x": true}}), None).await.unwrap();
+
+        let config = S3Config::from_env().unwrap();
+        let s3 = config.client();
+        // The bucket may already exist
+        let _ = s3.create_bucket().bucket(&config.bucket).send().await;
+
+        let client = LocalClient::tracked(mount_s3_backup_routes(rocket::build().manage(registry))).await.unwrap();
+        for format in ["json", "ndjson"] {
+            let mut request = client.post(format!("/admin/backup-s3?path=a&format={}", format));
+            if let Ok(token) = crate::settings::var("AUTH_TOKEN") {
+                request = request.header(rocket::http::Header::new("Authorization", format!("Bearer {}", token)));
+            }
+            let response = request.dispatch().await;
+            assert_eq!(response.status(), Status::Ok, "{}", format);
+            let report: serde_json::Value = response.into_json().await.unwrap();
+            assert_eq!(report["count"], 2);
+
+            let object = s3.get_object().bucket(&config.bucket).key(report["key"].as_str().unwrap()).send().await.unwrap();
+            let body = object.body.collect().await.unwrap().into_bytes();
+            assert_eq!(body.len() as u64, report["size"].as_u64().unwrap());
+            let entries: serde_json::Map<String, serde_json::Value> = if format == "json" {
+                serde_json::from_slice(&body).unwrap()
+            } else {
+                std::str::from_utf8(&body).unwrap().lines().map(|line| {
+                    let entry: serde_json::Value = serde_json::from_str(line).unwrap();
+                    (entry["key"].as_str().unwrap().to_string(), entry["value"].clone())
+                }).collect()
+            };
+            assert_eq!(serde_json::Value::Object(entries), serde_json::json!({"b": 1, "c": {"x": true}}));
+        }
+    }
+}
@@ -0,0 +1,227 @@
+// settings.rs
+use serde::Serialize;
+use std::collections::HashMap;
+use std::env;
+use std::sync::{Arc, OnceLock, RwLock};
+use utoipa::ToSchema;
+
+// =======================================================
+// Runtime configuration reload
+// =======================================================
+
+/// Settings that take effect without a restart when reloaded through POST /admin/reload
+pub const RELOADABLE_SETTINGS: &[&str] = &[
+    "AUTH_TOKEN",
+    "AUTH_TOKEN_LABEL",
+    "TOKEN_SCOPES",
+    "TOKEN_SCOPES_FILE",
+    "PREFIX_LOCK_TTL_MS",
+    "TTL_JITTER_PERCENT",
+    "CACHE_TTL_MS",
+    "ALLOW_ROOT_SCAN",
+    "STRICT_SET",
+    "MAX_KEYS_PER_OWNER",
+    "SLOW_OP_THRESHOLD_MS",
+    "REDIS_SCAN_COUNT",
+    "REDACT_PREFIXES",
+    "JSON_MAX_DEPTH",
+    "MAX_KEY_DEPTH",
+    "MAX_SEGMENT_LEN",
+    "JSON_MAX_BYTES",
+    "ERROR_VERBOSITY",
+];
+
+/// Settings that are only read at startup; changing them requires a restart
+pub const FIXED_SETTINGS: &[&str] = &[
+    "REDIS_URL",
+    "REDIS_UNIX_SOCKET",
+    "REDIS_HOST",
+    "REDIS_PORT",
+    "REDIS_DB",
+    "REDIS_USERNAME",
+    "REDIS_PASSWORD",
+    "REDIS_MIRROR_URL",
+    "REDIS_REPLICA_URL",
+    "REDIS_HASH_TAG",
+    "STARTUP_GATE",
+    "SLOW_REQUESTS_SIZE",
+    "PREFIX_DBS",
+    "KEY_SEPARATOR",
+    "VALUE_CODECS",
+    "VALUE_ENCRYPTION_KEY",
+    "VALUE_ENCRYPTION_OLD_KEYS",
+    "LAZY_CODEC_UPGRADE",
+    "HEDGE_READS",
+    "HEDGE_DELAY_MS",
+    "OWNER_TYPE",
+    "OWNER_ID",
+    "STORAGE",
+    "NORMALIZE_JSON",
+    "NORMALIZE_OWNER_CASE",
+    "TX_MAX_RETRIES",
+    "TX_RETRY_BASE_MS",
+    "READ_RETRIES",
+    "DUMP_BATCH_SIZE",
+    "DUMP_CONCURRENCY",
+    "CACHE_SIZE",
+    "STALE_CACHE_SIZE",
+    "STALE_CACHE_MAX_AGE_MS",
+    "CACHE_PUBSUB",
+    "MTIME_PRUNE_INTERVAL_SECS",
+    "ROCKET_PORT",
+    "RUST_LOG",
+    "LOG_DIR",
+];
+
+/// Outcome of a configuration reload
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ReloadReport {
+    /// Reloadable settings whose new value was applied
+    pub applied: Vec<String>,
+    /// Fixed settings that changed but only take effect after a restart
+    pub requires_restart: Vec<String>,
+}
+
+/// Values of the reloadable settings currently in effect
+/// Handlers read them from here instead of the process environment, which is never modified after startup
+#[derive(Debug, Default)]
+pub struct LiveConfig {
+    values: HashMap<&'static str, String>,
+}
+
+impl LiveConfig {
+    /// Take the reloadable settings from the process environment, as loaded at startup
+    fn from_env() -> Self {
+        LiveConfig {
+            values: RELOADABLE_SETTINGS.iter().filter_map(|name| env::var(name).ok().map(|value| (*name, value))).collect(),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.values.get(name).map(String::as_str)
+    }
+}
+
+// Live configuration, replaced as a whole by ConfigReloader::reload so that readers never see a partial reload
+static LIVE_CONFIG: OnceLock<RwLock<Arc<LiveConfig>>> = OnceLock::new();
+
+fn live_config() -> &'static RwLock<Arc<LiveConfig>> {
+    LIVE_CONFIG.get_or_init(|| RwLock::new(Arc::new(LiveConfig::from_env())))
+}
+
+/// Read a reloadable setting as of the last reload, with the signature of env::var
+pub fn var(name: &str) -> Result<String, env::VarError> {
+    debug_assert!(RELOADABLE_SETTINGS.contains(&name), "{} is not a reloadable setting", name);
+    live_config().read().unwrap().get(name).map(str::to_string).ok_or(env::VarError::NotPresent)
+}
+
+/// Read the variables of the .env file without touching the process environment
+fn read_env_file() -> HashMap<String, String> {
+    #[allow(deprecated)]
+    let entries = dotenv::dotenv_iter();
+    match entries {
+        Ok(iter) => iter
+            .filter_map(|item| match item {
+                Ok(entry) => Some(entry),
+                Err(e) => {
+                    warn!("Skipping invalid .env entry: {}", e);
+                    None
+                }
+            })
+            .collect(),
+        Err(e) => {
+            debug!("No .env file reloaded: {}", e);
+            HashMap::new()
+        }
+    }
+}
+
+/// Re-reads the .env file and swaps the live configuration, tracking which settings changed since startup
+pub struct ConfigReloader {
+    // Values of all settings at startup, keyed by variable name
+    startup: HashMap<&'static str, Option<String>>,
+}
+
+fn snapshot(names: &[&'static str]) -> HashMap<&'static str, Option<String>> {
+    names.iter().map(|name| (*name, env::var(name).ok())).collect()
+}
+
+impl ConfigReloader {
+    pub fn new() -> Self {
+        let mut startup = snapshot(RELOADABLE_SETTINGS);
+        startup.extend(snapshot(FIXED_SETTINGS));
+        live_config();
+        ConfigReloader { startup }
+    }
+
+    /// Re-read the .env file, atomically replace the live configuration and report what changed
+    /// Variables of the file take precedence over the environment the server was started with, as on startup
+    /// The caller is responsible for applying the reloadable settings to the live components
+    pub fn reload(&self) -> ReloadReport {
+        self.apply(read_env_file())
+    }
+
+    /// Replace the live configuration with the variables of a parsed .env file
+    fn apply(&self, file: HashMap<String, String>) -> ReloadReport {
+        let effective = |name: &str| file.get(name).cloned().or_else(|| self.startup.get(name).cloned().flatten());
+
+        let mut report = ReloadReport {
+            applied: Vec::new(),
+            requires_restart: Vec::new(),
+        };
+
+        // The write lock is held while the new configuration is built, so that concurrent reloads do not interleave
+        let mut live = live_config().write().unwrap();
+        let mut values = HashMap::new();
+        for name in RELOADABLE_SETTINGS {
+            let value = effective(name);
+            if live.get(name) != value.as_deref() {
+                info!("Reloadable setting changed: {}", name);
+                report.applied.push(name.to_string());
+            }
+            if let Some(value) = value {
+                values.insert(*name, value);
+            }
+        }
+        *live = Arc::new(LiveConfig { values });
+        drop(live);
+
+        // Fixed settings keep their startup value, so they are reported until the next restart
+        for name in FIXED_SETTINGS {
+            if self.startup.get(name).cloned().flatten() != effective(name) {
+                warn!("Setting {} changed but requires a restart to take effect", name);
+                report.requires_restart.push(name.to_string());
+            }
+        }
+
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reload_swaps_reloadable_settings_and_reports_fixed_ones() {
+        let reloader = ConfigReloader::new();
+        let file = HashMap::from([
+            ("STRICT_SET".to_string(), "true".to_string()),
+            ("REDIS_DB".to_string(), "7".to_string()),
+        ]);
+
+        let report = reloader.apply(file);
+
+        assert_eq!(var("STRICT_SET").as_deref(), Ok("true"));
+        assert!(report.applied.contains(&"STRICT_SET".to_string()));
+        assert_eq!(report.requires_restart, vec!["REDIS_DB".to_string()]);
+        // Fixed settings are never written to the live configuration or the process environment
+        assert_ne!(env::var("REDIS_DB").as_deref(), Ok("7"));
+
+        // Removing a variable from the file restores the value the server was started with
+        let report = reloader.apply(HashMap::new());
+        assert_eq!(var("STRICT_SET").ok(), env::var("STRICT_SET").ok());
+        assert!(report.applied.contains(&"STRICT_SET".to_string()));
+        assert!(report.requires_restart.is_empty());
+    }
+}
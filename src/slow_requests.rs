@@ -0,0 +1,173 @@
+// slow_requests.rs
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::{Data, Request, Response};
+use serde::Serialize;
+use std::env;
+use std::sync::Mutex;
+use std::time::Instant;
+use utoipa::ToSchema;
+
+use crate::error_detail::RequestId;
+use crate::redis_registry::now_ms;
+
+// =======================================================
+// Slowest recent requests
+// =======================================================
+// Every request is timed and the slowest ones are kept in memory, so GET /admin/slow-requests
+// shows them without searching the logs. Only the URL path is kept: key paths in query strings are not recorded
+
+/// Default number of requests kept
+const DEFAULT_SLOW_REQUESTS_SIZE: usize = 20;
+
+/// A request kept among the slowest ones
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct SlowRequest {
+    /// Request id, as returned in the X-Request-Id header
+    pub request_id: String,
+    pub method: String,
+    /// URL path, without the query string
+    pub path: String,
+    /// Name of the handler that served the request, absent when no route matched
+    pub operation: Option<String>,
+    /// Response status code
+    pub status: u16,
+    pub duration_ms: f64,
+    /// Time the response was sent, in milliseconds since the unix epoch
+    pub finished_at: u64,
+}
+
+/// Bounded list of the slowest requests, slowest first, managed by Rocket
+pub struct SlowRequests {
+    capacity: usize,
+    entries: Mutex<Vec<SlowRequest>>,
+}
+
+impl SlowRequests {
+    /// Read the number of requests to keep from SLOW_REQUESTS_SIZE (default: 20, 0 disables recording)
+    pub fn from_env() -> Self {
+        let capacity = match env::var("SLOW_REQUESTS_SIZE").ok().map(|s| s.parse::<usize>()) {
+            Some(Ok(size)) => size,
+            Some(Err(e)) => {
+                warn!("Invalid SLOW_REQUESTS_SIZE value: {}. Using {}", e, DEFAULT_SLOW_REQUESTS_SIZE);
+                DEFAULT_SLOW_REQUESTS_SIZE
+            }
+            None => DEFAULT_SLOW_REQUESTS_SIZE,
+        };
+        if capacity == 0 {
+            info!("Slow request recording is disabled");
+        }
+        SlowRequests {
+            capacity,
+            entries: Mutex::new(Vec::with_capacity(capacity + 1)),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.capacity > 0
+    }
+
+    /// Keep a request if it is slower than the fastest one kept, dropping that one when the list is full
+    fn record(&self, request: SlowRequest) {
+        let Ok(mut entries) = self.entries.lock() else {
+            return;
+        };
+        if entries.len() >= self.capacity && entries.last().is_some_and(|fastest| fastest.duration_ms >= request.duration_ms) {
+            return;
+        }
+        let position = entries.partition_point(|entry| entry.duration_ms >= request.duration_ms);
+        entries.insert(position, request);
+        entries.truncate(self.capacity);
+    }
+
+    /// The requests kept, slowest first
+    pub fn snapshot(&self) -> Vec<SlowRequest> {
+        self.entries.lock().map(|entries| entries.clone()).unwrap_or_default()
+    }
+
+    /// Forget every request kept
+    pub fn clear(&self) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.clear();
+        }
+    }
+}
+
+// Start of the current request, kept in the request-local cache
+struct RequestStart(Option<Instant>);
+
+/// Fairing timing every request and recording the slowest ones in the managed SlowRequests
+pub struct SlowRequestsFairing;
+
+#[rocket::async_trait]
+impl Fairing for SlowRequestsFairing {
+    fn info(&self) -> Info {
+        Info {
+            name: "Slow requests",
+            kind: Kind::Request | Kind::Response,
+        }
+    }
+
+    async fn on_request(&self, request: &mut Request<'_>, _data: &mut Data<'_>) {
+        request.local_cache(|| RequestStart(Some(Instant::now())));
+    }
+
+    async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
+        let Some(slow_requests) = request.rocket().state::<SlowRequests>().filter(|slow| slow.is_enabled()) else {
+            return;
+        };
+        let Some(start) = request.local_cache(|| RequestStart(None)).0 else {
+            return;
+        };
+
+        slow_requests.record(SlowRequest {
+            request_id: RequestId::of(request),
+            method: request.method().to_string(),
+            path: request.uri().path().to_string(),
+            operation: request.route().and_then(|route| route.name.as_ref()).map(|name| name.to_string()),
+            status: response.status().code,
+            duration_ms: start.elapsed().as_secs_f64() * 1000.0,
+            finished_at: now_ms(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rocket::local::asynchronous::Client;
+    use rocket::{get, routes};
+
+    #[get("/fast")]
+    fn fast() -> &'static str {
+        "fast"
+    }
+
+    #[get("/slow?<ms>")]
+    async fn slow(ms: u64) -> &'static str {
+        rocket::tokio::time::sleep(std::time::Duration::from_millis(ms)).await;
+        "slow"
+    }
+
+    #[rocket::async_test]
+    async fn slowest_requests_are_kept_slowest_first() {
+        let slow_requests = SlowRequests { capacity: 2, entries: Mutex::new(Vec::new()) };
+        let rocket = rocket::build()
+            .mount("/", routes![fast, slow])
+            .manage(slow_requests)
+            .attach(SlowRequestsFairing);
+        let client = Client::tracked(rocket).await.unwrap();
+
+        client.get("/fast").dispatch().await;
+        client.get("/slow?ms=60").dispatch().await;
+        client.get("/slow?ms=30").dispatch().await;
+        client.get("/fast").dispatch().await;
+
+        let recorded = client.rocket().state::<SlowRequests>().unwrap().snapshot();
+        assert_eq!(recorded.len(), 2);
+        assert_eq!(recorded[0].path, "/slow");
+        assert_eq!(recorded[0].operation.as_deref(), Some("slow"));
+        assert_eq!(recorded[0].status, 200);
+        assert!(recorded[0].duration_ms >= 60.0, "{:?}", recorded[0]);
+        assert!(recorded[1].duration_ms >= 30.0 && recorded[1].duration_ms <= recorded[0].duration_ms, "{:?}", recorded);
+    }
+}
@@ -0,0 +1,130 @@
+// startup_gate.rs
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::http::{ContentType, Header, Status};
+use rocket::tokio::time::sleep;
+use rocket::{Orbit, Request, Response, Rocket};
+use std::env;
+use std::io::Cursor;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::redis_registry::AsyncRegistry;
+use crate::redis_registry_api::ErrorResponse;
+
+// =======================================================
+// Startup gate
+// =======================================================
+// Redis connections are opened lazily, so the server accepts traffic before Redis is known to be reachable.
+// With STARTUP_GATE=true, registry routes are rejected with 503 until the first successful PING
+
+/// Routes rejected until Redis has been reached
+const GATED_PREFIX: &str = "/registry/";
+
+/// Delay between PINGs while Redis has not been reached yet, also suggested to clients in Retry-After
+const PING_INTERVAL_SECS: u64 = 1;
+
+/// Readiness of the server, managed by Rocket
+#[derive(Clone)]
+pub struct StartupGate {
+    ready: Arc<AtomicBool>,
+}
+
+// Marker left in the request-local cache when a request was rejected before readiness
+struct Rejected(bool);
+
+impl StartupGate {
+    /// Read the STARTUP_GATE environment variable (default: false, the server is ready immediately)
+    pub fn from_env() -> Self {
+        let enabled = env::var("STARTUP_GATE")
+            .map(|value| value.eq_ignore_ascii_case("true"))
+            .unwrap_or(false);
+        if enabled {
+            info!("Startup gate enabled: registry routes return 503 until Redis is reachable");
+        }
+        StartupGate::new(enabled)
+    }
+
+    /// Create a gate that is closed until mark_ready when enabled, and open otherwise
+    pub fn new(enabled: bool) -> Self {
+        StartupGate {
+            ready: Arc::new(AtomicBool::new(!enabled)),
+        }
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.ready.load(Ordering::Relaxed)
+    }
+
+    /// Open the gate; it never closes again
+    pub fn mark_ready(&self) {
+        if !self.ready.swap(true, Ordering::Relaxed) {
+            info!("Redis is reachable, startup gate opened");
+        }
+    }
+
+    /// Check whether a request must be rejected because Redis has not been reached yet
+    /// Only registry routes are gated; admin and health routes keep working
+    pub fn rejects(&self, request: &Request<'_>) -> bool {
+        if self.is_ready() || !request.uri().path().starts_with(GATED_PREFIX) {
+            return false;
+        }
+        request.local_cache(|| Rejected(true));
+        true
+    }
+}
+
+/// Fairing pinging Redis after launch until it answers, and turning the 503 of gated requests
+/// into our error body with a Retry-After header
+pub struct StartupGateFairing;
+
+#[rocket::async_trait]
+impl Fairing for StartupGateFairing {
+    fn info(&self) -> Info {
+        Info {
+            name: "Startup gate",
+            kind: Kind::Liftoff | Kind::Response,
+        }
+    }
+
+    async fn on_liftoff(&self, rocket: &Rocket<Orbit>) {
+        let Some(gate) = rocket.state::<StartupGate>().cloned() else {
+            return;
+        };
+        if gate.is_ready() {
+            return;
+        }
+        let Some(registry) = rocket.state::<AsyncRegistry>().cloned() else {
+            error!("AsyncRegistry is not managed by Rocket");
+            return;
+        };
+
+        let shutdown = rocket.shutdown();
+        rocket::tokio::spawn(async move {
+            while !gate.is_ready() {
+                match registry.ping().await {
+                    Ok(_) => gate.mark_ready(),
+                    Err(e) => {
+                        debug!("Redis is not reachable yet: {}", e);
+                        rocket::tokio::select! {
+                            _ = sleep(Duration::from_secs(PING_INTERVAL_SECS)) => {},
+                            _ = shutdown.clone() => return,
+                        }
+                    }
+                }
+            }
+        });
+    }
+
+    async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
+        if !request.local_cache(|| Rejected(false)).0 {
+            return;
+        }
+
+        let body = serde_json::to_string(&ErrorResponse::new("initializing".to_string())).unwrap_or_default();
+        response.set_status(Status::ServiceUnavailable);
+        response.set_header(ContentType::JSON);
+        response.set_header(Header::new("Retry-After", PING_INTERVAL_SECS.to_string()));
+        response.set_sized_body(body.len(), Cursor::new(body));
+    }
+}
@@ -0,0 +1,126 @@
+// transform.rs
+use rocket::serde::json::Value as JsonValue;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+// =======================================================
+// Registered value transforms for bulk migrations
+// =======================================================
+
+/// Named transform applied to every value under a prefix
+/// Transforms only apply to JSON object values; other values are reported as failures
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+#[serde(tag = "name")]
+pub enum Transform {
+    /// Add a field if it is not present yet
+    #[serde(rename = "add_field")]
+    Add {
+        field: String,
+        value: JsonValue,
+    },
+    /// Set a field, overwriting any existing value
+    #[serde(rename = "set_field")]
+    Set {
+        field: String,
+        value: JsonValue,
+    },
+    /// Remove a field if present
+    #[serde(rename = "remove_field")]
+    Remove {
+        field: String,
+    },
+    /// Rename a field if present, overwriting the target field
+    #[serde(rename = "rename_field")]
+    Rename {
+        from: String,
+        to: String,
+    },
+}
+
+impl Transform {
+    /// Compute the transformed value
+    pub fn apply(&self, value: &JsonValue) -> Result<JsonValue, String> {
+        let JsonValue::Object(map) = value else {
+            return Err("Value is not a JSON object".to_string());
+        };
+
+        let mut map = map.clone();
+        match self {
+            Transform::Add { field, value } => {
+                map.entry(field.clone()).or_insert_with(|| value.clone());
+            }
+            Transform::Set { field, value } => {
+                map.insert(field.clone(), value.clone());
+            }
+            Transform::Remove { field } => {
+                map.remove(field);
+            }
+            Transform::Rename { from, to } => {
+                if let Some(value) = map.remove(from) {
+                    map.insert(to.clone(), value);
+                }
+            }
+        }
+        Ok(JsonValue::Object(map))
+    }
+}
+
+/// A key that could not be transformed
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct TransformFailure {
+    /// Relative key path
+    pub key: String,
+    pub error: String,
+}
+
+/// Value of a key before and after the transform, reported by dry runs
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct TransformSample {
+    /// Relative key path
+    pub key: String,
+    pub before: JsonValue,
+    pub after: JsonValue,
+}
+
+/// Outcome of a bulk transform
+#[derive(Debug, Clone, Default, Serialize, ToSchema)]
+pub struct TransformReport {
+    /// Number of values changed (or that would change in a dry run)
+    pub transformed: usize,
+    /// Number of values the transform left as they were
+    pub unchanged: usize,
+    pub failures: Vec<TransformFailure>,
+    /// Sample of before/after values, only filled in dry runs
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub samples: Vec<TransformSample>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn transform(json: JsonValue) -> Transform {
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn add_field_keeps_existing_values() {
+        let add = transform(json!({"name": "add_field", "field": "enabled", "value": true}));
+        assert_eq!(add.apply(&json!({"id": 1})).unwrap(), json!({"id": 1, "enabled": true}));
+        assert_eq!(add.apply(&json!({"enabled": false})).unwrap(), json!({"enabled": false}));
+        assert!(add.apply(&json!([1])).is_err());
+    }
+
+    #[test]
+    fn set_remove_and_rename_fields() {
+        let value = json!({"a": 1, "b": 2});
+        let set = transform(json!({"name": "set_field", "field": "a", "value": 3}));
+        assert_eq!(set.apply(&value).unwrap(), json!({"a": 3, "b": 2}));
+        let remove = transform(json!({"name": "remove_field", "field": "a"}));
+        assert_eq!(remove.apply(&value).unwrap(), json!({"b": 2}));
+        let rename = transform(json!({"name": "rename_field", "from": "a", "to": "b"}));
+        assert_eq!(rename.apply(&value).unwrap(), json!({"b": 1}));
+        assert_eq!(rename.apply(&json!({"c": 1})).unwrap(), json!({"c": 1}));
+    }
+}
@@ -0,0 +1,258 @@
+// value_codec.rs
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use rand::Rng;
+use std::borrow::Cow;
+use std::env;
+use std::io::{Read, Write};
+
+use crate::redis_registry::key_separator;
+
+// =======================================================
+// Storage codecs selected per key path prefix
+// =======================================================
+// Values of prefixes mapped in VALUE_CODECS are compressed or encrypted before they are stored.
+// Encoded values start with a header character naming their codec, followed by the base64 payload;
+// plain JSON never starts with it, so values are decoded by their header, whatever the current mapping.
+// Values encrypted with a retired key stay readable through VALUE_ENCRYPTION_OLD_KEYS
+
+// Header characters of encoded values
+const GZIP_HEADER: char = '\u{1}';
+const ENCRYPT_HEADER: char = '\u{2}';
+
+// Length of the random AES-GCM nonce stored in front of every encrypted value
+const NONCE_LEN: usize = 12;
+
+/// Encoding of stored values
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueCodec {
+    /// Plain JSON, as stored without a mapping
+    Identity,
+    /// Gzip-compressed JSON
+    Gzip,
+    /// JSON encrypted with AES-256-GCM using VALUE_ENCRYPTION_KEY
+    Encrypt,
+}
+
+impl ValueCodec {
+    fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "identity" | "json" => Some(ValueCodec::Identity),
+            "gzip" => Some(ValueCodec::Gzip),
+            "encrypt" => Some(ValueCodec::Encrypt),
+            _ => None,
+        }
+    }
+}
+
+/// Codecs of the configured key path prefixes, with the encryption key of the encrypt codec
+pub struct CodecRegistry {
+    // Prefixes split into path segments, longest first so that the most specific mapping wins
+    prefixes: Vec<(Vec<String>, ValueCodec)>,
+    cipher: Option<Aes256Gcm>,
+    // Retired keys, only used to decrypt values that were not re-encrypted with the current key yet
+    old_ciphers: Vec<Aes256Gcm>,
+}
+
+/// Read a base64-encoded 32-byte AES-256-GCM key
+fn parse_key(name: &str, key: &str) -> Result<Aes256Gcm, String> {
+    let key = BASE64
+        .decode(key.trim())
+        .map_err(|e| format!("{} is not valid base64: {}", name, e))?;
+    Aes256Gcm::new_from_slice(&key).map_err(|_| format!("{} must be 32 bytes, got {}", name, key.len()))
+}
+
+impl CodecRegistry {
+    /// Read the prefix mapping from VALUE_CODECS (comma-separated `prefix=codec` pairs, e.g. `secrets=encrypt,logs=gzip`)
+    /// and the base64-encoded 32-byte key of the encrypt codec from VALUE_ENCRYPTION_KEY,
+    /// with retired keys still accepted for decryption from VALUE_ENCRYPTION_OLD_KEYS (comma-separated)
+    /// Fails on unknown codecs, on an invalid key, and when a prefix is encrypted without a key
+    pub fn from_env() -> Result<Self, String> {
+        let mut prefixes = Vec::new();
+        for pair in env::var("VALUE_CODECS").unwrap_or_default().split(',') {
+            let pair = pair.trim();
+            if pair.is_empty() {
+                continue;
+            }
+            let Some((prefix, name)) = pair.split_once('=') else {
+                return Err(format!("Invalid VALUE_CODECS entry (expected prefix=codec): {}", pair));
+            };
+            let Some(codec) = ValueCodec::parse(name.trim()) else {
+                return Err(format!("Unknown codec in VALUE_CODECS: {}", name.trim()));
+            };
+            let segments: Vec<String> = prefix
+                .split(key_separator())
+                .map(str::trim)
+                .filter(|segment| !segment.is_empty())
+                .map(str::to_string)
+                .collect();
+            prefixes.push((segments, codec));
+        }
+        prefixes.sort_by_key(|(segments, _)| std::cmp::Reverse(segments.len()));
+
+        let cipher = match env::var("VALUE_ENCRYPTION_KEY").ok().filter(|key| !key.is_empty()) {
+            Some(key) => Some(parse_key("VALUE_ENCRYPTION_KEY", &key)?),
+            None => None,
+        };
+        let old_ciphers = env::var("VALUE_ENCRYPTION_OLD_KEYS")
+            .unwrap_or_default()
+            .split(',')
+            .filter(|key| !key.trim().is_empty())
+            .map(|key| parse_key("VALUE_ENCRYPTION_OLD_KEYS", key))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if cipher.is_none() && prefixes.iter().any(|(_, codec)| *codec == ValueCodec::Encrypt) {
+            return Err("VALUE_CODECS encrypts a prefix but VALUE_ENCRYPTION_KEY is not set".to_string());
+        }
+
+        Ok(CodecRegistry { prefixes, cipher, old_ciphers })
+    }
+
+    /// Configured prefixes (joined with the key separator) and their codecs
+    pub fn mappings(&self) -> impl Iterator<Item = (String, ValueCodec)> + '_ {
+        self.prefixes.iter().map(|(segments, codec)| (segments.join(key_separator()), *codec))
+    }
+
+    /// Codec of a key path relative to the owner prefix (e.g. "secrets/db/password")
+    pub fn codec_for(&self, path: &str) -> ValueCodec {
+        let segments: Vec<&str> = path.split(key_separator()).collect();
+        self.prefixes
+            .iter()
+            .find(|(prefix, _)| prefix.len() <= segments.len() && prefix.iter().zip(&segments).all(|(a, b)| a == b))
+            .map(|(_, codec)| *codec)
+            .unwrap_or(ValueCodec::Identity)
+    }
+
+    /// Encode a serialized JSON value with the specified codec
+    pub fn encode(&self, codec: ValueCodec, json: String) -> Result<String, String> {
+        match codec {
+            ValueCodec::Identity => Ok(json),
+            ValueCodec::Gzip => {
+                let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+                encoder
+                    .write_all(json.as_bytes())
+                    .and_then(|_| encoder.finish())
+                    .map(|compressed| format!("{}{}", GZIP_HEADER, BASE64.encode(compressed)))
+                    .map_err(|e| format!("Failed to compress value: {}", e))
+            }
+            ValueCodec::Encrypt => {
+                let cipher = self.cipher.as_ref().ok_or("No VALUE_ENCRYPTION_KEY configured")?;
+                let mut nonce = [0u8; NONCE_LEN];
+                rand::thread_rng().fill(&mut nonce);
+                let ciphertext = cipher
+                    .encrypt(Nonce::from_slice(&nonce), json.as_bytes())
+                    .map_err(|e| format!("Failed to encrypt value: {}", e))?;
+                let mut payload = nonce.to_vec();
+                payload.extend(ciphertext);
+                Ok(format!("{}{}", ENCRYPT_HEADER, BASE64.encode(payload)))
+            }
+        }
+    }
+
+    /// Decode a stored value into serialized JSON, using the codec named by its header
+    pub fn decode<'a>(&self, stored: &'a str) -> Result<Cow<'a, str>, String> {
+        self.decode_with_status(stored).map(|(json, _, _)| json)
+    }
+
+    /// Decode a stored value of a key path relative to the owner prefix, also reporting whether it is outdated:
+    /// stored with another codec than the one currently mapped to its path, or encrypted with a retired key
+    pub fn decode_for<'a>(&self, path: &str, stored: &'a str) -> Result<(Cow<'a, str>, bool), String> {
+        let (json, codec, old_key) = self.decode_with_status(stored)?;
+        Ok((json, old_key || codec != self.codec_for(path)))
+    }
+
+    // Decode a stored value, returning the codec it was stored with and whether a retired key decrypted it
+    fn decode_with_status<'a>(&self, stored: &'a str) -> Result<(Cow<'a, str>, ValueCodec, bool), String> {
+        let Some(header) = stored.chars().next().filter(|c| *c == GZIP_HEADER || *c == ENCRYPT_HEADER) else {
+            return Ok((Cow::Borrowed(stored), ValueCodec::Identity, false));
+        };
+        let payload = BASE64
+            .decode(&stored[header.len_utf8()..])
+            .map_err(|e| format!("Encoded value is not valid base64: {}", e))?;
+
+        if header == GZIP_HEADER {
+            let mut json = String::new();
+            GzDecoder::new(payload.as_slice())
+                .read_to_string(&mut json)
+                .map_err(|e| format!("Failed to decompress value: {}", e))?;
+            return Ok((Cow::Owned(json), ValueCodec::Gzip, false));
+        }
+
+        if self.cipher.is_none() && self.old_ciphers.is_empty() {
+            return Err("Value is encrypted but no VALUE_ENCRYPTION_KEY is configured".to_string());
+        }
+        if payload.len() < NONCE_LEN {
+            return Err("Encrypted value is truncated".to_string());
+        }
+        let (nonce, ciphertext) = payload.split_at(NONCE_LEN);
+        let (json, old_key) = self
+            .cipher
+            .iter()
+            .map(|cipher| (cipher, false))
+            .chain(self.old_ciphers.iter().map(|cipher| (cipher, true)))
+            .find_map(|(cipher, old_key)| cipher.decrypt(Nonce::from_slice(nonce), ciphertext).ok().map(|json| (json, old_key)))
+            .ok_or_else(|| "Failed to decrypt value (wrong VALUE_ENCRYPTION_KEY?)".to_string())?;
+        String::from_utf8(json)
+            .map(|json| (Cow::Owned(json), ValueCodec::Encrypt, old_key))
+            .map_err(|e| format!("Decrypted value is not UTF-8: {}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cipher(byte: u8) -> Aes256Gcm {
+        Aes256Gcm::new_from_slice(&[byte; 32]).unwrap()
+    }
+
+    fn registry(cipher: Option<Aes256Gcm>, old_ciphers: Vec<Aes256Gcm>) -> CodecRegistry {
+        // Longest prefix first, as from_env sorts them
+        let prefixes = [("logs/plain", ValueCodec::Identity), ("secrets", ValueCodec::Encrypt), ("logs", ValueCodec::Gzip)]
+            .into_iter()
+            .map(|(prefix, codec)| (prefix.split('/').map(str::to_string).collect(), codec))
+            .collect();
+        CodecRegistry { prefixes, cipher, old_ciphers }
+    }
+
+    #[test]
+    fn the_most_specific_prefix_selects_the_codec() {
+        let codecs = registry(Some(cipher(1)), Vec::new());
+        assert_eq!(codecs.codec_for("secrets/db"), ValueCodec::Encrypt);
+        assert_eq!(codecs.codec_for("logs/app"), ValueCodec::Gzip);
+        assert_eq!(codecs.codec_for("logs/plain/app"), ValueCodec::Identity);
+        assert_eq!(codecs.codec_for("secretsx/db"), ValueCodec::Identity);
+    }
+
+    #[test]
+    fn encoded_values_round_trip() {
+        let codecs = registry(Some(cipher(1)), Vec::new());
+        let json = r#"{"user":"john","tags":["a","b"]}"#;
+        for codec in [ValueCodec::Identity, ValueCodec::Gzip, ValueCodec::Encrypt] {
+            let stored = codecs.encode(codec, json.to_string()).unwrap();
+            assert_eq!(stored == json, codec == ValueCodec::Identity);
+            assert_eq!(codecs.decode(&stored).unwrap(), json);
+        }
+    }
+
+    #[test]
+    fn values_are_outdated_under_another_codec_or_a_retired_key() {
+        let json = "42".to_string();
+        let retired = registry(Some(cipher(1)), Vec::new()).encode(ValueCodec::Encrypt, json.clone()).unwrap();
+        let codecs = registry(Some(cipher(2)), vec![cipher(1)]);
+
+        let current = codecs.encode(ValueCodec::Encrypt, json.clone()).unwrap();
+        assert_eq!(codecs.decode_for("secrets/a", &current).unwrap(), (Cow::Borrowed("42"), false));
+        assert_eq!(codecs.decode_for("secrets/a", &retired).unwrap(), (Cow::Borrowed("42"), true));
+        assert_eq!(codecs.decode_for("logs/a", &current).unwrap(), (Cow::Borrowed("42"), true));
+        assert_eq!(codecs.decode_for("other", "42").unwrap(), (Cow::Borrowed("42"), false));
+
+        // Without the key that encrypted it, a value cannot be read
+        assert!(registry(Some(cipher(3)), Vec::new()).decode(&current).is_err());
+    }
+}
@@ -0,0 +1,66 @@
+// value_match.rs
+use rocket::serde::json::Value as JsonValue;
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+// =======================================================
+// Value predicates for selective bulk operations
+// =======================================================
+
+/// Matches values whose field equals the given value
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct ValueMatch {
+    /// Dot-separated path of a field in object values (e.g. "status" or "owner.team"); omit to compare the whole value
+    #[serde(default)]
+    pub field: Option<String>,
+    /// Value the field must be equal to
+    pub value: JsonValue,
+}
+
+impl ValueMatch {
+    /// Check whether a stored value matches; values without the field never match
+    pub fn matches(&self, value: &JsonValue) -> bool {
+        let selected = match self.field.as_deref() {
+            None | Some("") => Some(value),
+            Some(field) => field
+                .split('.')
+                .try_fold(value, |current, segment| current.as_object().and_then(|map| map.get(segment))),
+        };
+        selected == Some(&self.value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn filter(field: Option<&str>, value: JsonValue) -> ValueMatch {
+        ValueMatch { field: field.map(str::to_string), value }
+    }
+
+    #[test]
+    fn fields_are_selected_by_dotted_path() {
+        let value = json!({"status": "active", "owner": {"team": "core", "size": 3}});
+        assert!(filter(Some("status"), json!("active")).matches(&value));
+        assert!(filter(Some("owner.team"), json!("core")).matches(&value));
+        assert!(filter(Some("owner"), json!({"team": "core", "size": 3})).matches(&value));
+        assert!(!filter(Some("owner.size"), json!("3")).matches(&value));
+        assert!(!filter(Some("status"), json!("inactive")).matches(&value));
+    }
+
+    #[test]
+    fn values_without_the_field_never_match() {
+        assert!(!filter(Some("status"), JsonValue::Null).matches(&json!({"other": 1})));
+        assert!(!filter(Some("owner.team"), json!("core")).matches(&json!({"owner": "core"})));
+        assert!(!filter(Some("status"), json!("active")).matches(&json!("active")));
+        assert!(filter(Some("status"), JsonValue::Null).matches(&json!({"status": null})));
+    }
+
+    #[test]
+    fn a_missing_or_empty_field_compares_the_whole_value() {
+        assert!(filter(None, json!(5)).matches(&json!(5)));
+        assert!(filter(Some(""), json!([1, 2])).matches(&json!([1, 2])));
+        assert!(!filter(None, json!(5)).matches(&json!({"value": 5})));
+    }
+}
@@ -0,0 +1,334 @@
+// wire_format.rs
+use async_compression::tokio::bufread::GzipDecoder;
+use rocket::data::{ByteUnit, Data, FromData, Limits, Outcome};
+use rocket::http::{ContentType, MediaType, Status};
+use rocket::serde::json::Value as JsonValue;
+use rocket::tokio::io::{AsyncBufRead, AsyncReadExt, BufReader};
+use rocket::request::{self, FromRequest};
+use rocket::Request;
+use std::pin::Pin;
+
+use crate::settings;
+
+// =======================================================
+// MessagePack content negotiation
+// =======================================================
+// Values are always stored as JSON in Redis; MessagePack is only used on the wire
+
+/// Media type of MessagePack request and response bodies
+pub fn msgpack_content_type() -> ContentType {
+    ContentType::new("application", "msgpack")
+}
+
+fn is_msgpack(media_type: &MediaType) -> bool {
+    media_type.top() == "application" && (media_type.sub() == "msgpack" || media_type.sub() == "x-msgpack")
+}
+
+/// Check whether the client asked for a MessagePack response (`Accept: application/msgpack`)
+pub fn accepts_msgpack(request: &Request<'_>) -> bool {
+    request
+        .accept()
+        .map(|accept| accept.media_types().any(is_msgpack))
+        .unwrap_or(false)
+}
+
+/// Encode a value as MessagePack, with map keys encoded as strings like in JSON
+pub fn to_msgpack(value: &JsonValue) -> Result<Vec<u8>, rmp_serde::encode::Error> {
+    rmp_serde::to_vec_named(value)
+}
+
+/// Default maximum nesting depth of request bodies (JSON_MAX_DEPTH)
+const DEFAULT_JSON_MAX_DEPTH: usize = 64;
+
+/// Parse limits of request bodies, read from the environment on every request so that they can be reloaded
+struct JsonLimits {
+    max_depth: usize,
+    max_bytes: Option<u64>,
+}
+
+impl JsonLimits {
+    fn from_env() -> Self {
+        let max_depth = match settings::var("JSON_MAX_DEPTH").ok().map(|s| s.parse::<usize>()) {
+            Some(Ok(depth)) if depth > 0 => depth,
+            Some(_) => {
+                warn!("Invalid JSON_MAX_DEPTH value. Using default {}", DEFAULT_JSON_MAX_DEPTH);
+                DEFAULT_JSON_MAX_DEPTH
+            }
+            None => DEFAULT_JSON_MAX_DEPTH,
+        };
+        let max_bytes = match settings::var("JSON_MAX_BYTES").ok().map(|s| s.parse::<ByteUnit>()) {
+            Some(Ok(bytes)) => Some(bytes.as_u64()),
+            Some(Err(e)) => {
+                warn!("Invalid JSON_MAX_BYTES value: {}. Using the json limit only", e);
+                None
+            }
+            None => None,
+        };
+        JsonLimits { max_depth, max_bytes }
+    }
+}
+
+/// Check whether a JSON document nests arrays and objects deeper than max_depth, without parsing it
+/// Runs in constant memory, so over-deep documents are rejected before serde_json recurses into them
+fn json_exceeds_depth(bytes: &[u8], max_depth: usize) -> bool {
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    for &byte in bytes {
+        if in_string {
+            match byte {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match byte {
+            b'"' => in_string = true,
+            b'[' | b'{' => {
+                depth += 1;
+                if depth > max_depth {
+                    return true;
+                }
+            }
+            b']' | b'}' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    false
+}
+
+/// Nesting depth of arrays and objects in a decoded value
+fn value_depth(value: &JsonValue) -> usize {
+    match value {
+        JsonValue::Array(items) => 1 + items.iter().map(value_depth).max().unwrap_or(0),
+        JsonValue::Object(map) => 1 + map.values().map(value_depth).max().unwrap_or(0),
+        _ => 0,
+    }
+}
+
+/// Request body decoded from JSON or, with `Content-Type: application/msgpack`, from MessagePack
+pub struct WireValue(pub JsonValue);
+
+impl WireValue {
+    pub fn into_inner(self) -> JsonValue {
+        self.0
+    }
+}
+
+#[derive(Debug)]
+#[allow(dead_code)]
+pub enum WireValueError {
+    Io(std::io::Error),
+    TooLarge,
+    TooDeep,
+    MsgPack(rmp_serde::decode::Error),
+    Json(String),
+}
+
+#[rocket::async_trait]
+impl<'r> FromData<'r> for WireValue {
+    type Error = WireValueError;
+
+    async fn from_data(request: &'r Request<'_>, data: Data<'r>) -> Outcome<'r, Self> {
+        let is_msgpack_body = request
+            .content_type()
+            .map(|content_type| is_msgpack(content_type.media_type()))
+            .unwrap_or(false);
+
+        let encoding = BodyEncoding::of(request);
+        let limits = JsonLimits::from_env();
+
+        // All bodies share the JSON size limit (lowered by JSON_MAX_BYTES), which for gzip applies to the decompressed size
+        let mut limit = request.limits().get("json").unwrap_or(Limits::JSON);
+        if let Some(max_bytes) = limits.max_bytes {
+            limit = limit.min(ByteUnit::from(max_bytes));
+        }
+        // One byte past the limit is read, so that an over-large body is told apart from one at the limit
+        let mut bytes = Vec::new();
+        if let Err(e) = encoding.open(data, limit + ByteUnit::Byte(1)).take(limit.as_u64() + 1).read_to_end(&mut bytes).await {
+            warn!("Failed to read request body: {}", e);
+            return Outcome::Error((Status::BadRequest, WireValueError::Io(e)));
+        }
+        if bytes.len() as u64 > limit.as_u64() {
+            warn!("Request body exceeds the {} limit", limit);
+            return Outcome::Error((Status::PayloadTooLarge, WireValueError::TooLarge));
+        }
+
+        if !is_msgpack_body {
+            if json_exceeds_depth(&bytes, limits.max_depth) {
+                warn!("JSON request body is nested deeper than {} levels", limits.max_depth);
+                return Outcome::Error((Status::BadRequest, WireValueError::TooDeep));
+            }
+            return match serde_json::from_slice::<JsonValue>(&bytes) {
+                Ok(value) => Outcome::Success(WireValue(value)),
+                Err(e) => {
+                    warn!("Failed to parse JSON request body: {}", e);
+                    Outcome::Error((Status::BadRequest, WireValueError::Json(e.to_string())))
+                }
+            };
+        }
+
+        match rmp_serde::from_slice::<JsonValue>(&bytes) {
+            Ok(value) if value_depth(&value) > limits.max_depth => {
+                warn!("MessagePack request body is nested deeper than {} levels", limits.max_depth);
+                Outcome::Error((Status::BadRequest, WireValueError::TooDeep))
+            }
+            Ok(value) => {
+                trace!("Decoded MessagePack request body of {} bytes", bytes.len());
+                Outcome::Success(WireValue(value))
+            }
+            Err(e) => {
+                warn!("Failed to decode MessagePack request body: {}", e);
+                Outcome::Error((Status::BadRequest, WireValueError::MsgPack(e)))
+            }
+        }
+    }
+}
+
+// =======================================================
+// Gzip request bodies
+// =======================================================
+
+/// Content encoding of a request body, `Content-Encoding: gzip` or none
+#[derive(Debug, Clone, Copy)]
+pub struct BodyEncoding {
+    pub gzip: bool,
+}
+
+impl BodyEncoding {
+    pub fn of(request: &Request<'_>) -> Self {
+        let gzip = request
+            .headers()
+            .get_one("Content-Encoding")
+            .map(|encoding| encoding.trim().eq_ignore_ascii_case("gzip"))
+            .unwrap_or(false);
+        BodyEncoding { gzip }
+    }
+
+    /// Open a request body, transparently decompressing it when it is gzip-compressed
+    /// At most `limit` bytes of the raw body are read; callers bound the decompressed size themselves
+    pub fn open<'r>(&self, data: Data<'r>, limit: ByteUnit) -> Pin<Box<dyn AsyncBufRead + Send + 'r>> {
+        let body = BufReader::new(data.open(limit));
+        if self.gzip {
+            trace!("Decompressing gzip request body");
+            Box::pin(BufReader::new(GzipDecoder::new(body)))
+        } else {
+            Box::pin(body)
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for BodyEncoding {
+    type Error = std::convert::Infallible;
+
+    async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        request::Outcome::Success(BodyEncoding::of(request))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rocket::http::Header;
+    use rocket::local::asynchronous::Client;
+    use rocket::serde::json::Json;
+    use rocket::{post, routes};
+    use serde_json::json;
+
+    #[post("/echo", data = "<value>")]
+    fn echo(value: WireValue) -> Json<JsonValue> {
+        Json(value.into_inner())
+    }
+
+    // Local client of a route echoing the decoded body, with a json limit of `limit` bytes
+    async fn client(limit: ByteUnit) -> Client {
+        let config = rocket::Config {
+            limits: Limits::default().limit("json", limit),
+            ..rocket::Config::debug_default()
+        };
+        Client::tracked(rocket::custom(config).mount("/", routes![echo])).await.unwrap()
+    }
+
+    async fn gzip(body: &[u8]) -> Vec<u8> {
+        let mut compressed = Vec::new();
+        async_compression::tokio::bufread::GzipEncoder::new(body).read_to_end(&mut compressed).await.unwrap();
+        compressed
+    }
+
+    fn nested(depth: usize) -> String {
+        format!("{}1{}", "[".repeat(depth), "]".repeat(depth))
+    }
+
+    #[test]
+    fn depth_is_counted_outside_of_strings() {
+        assert!(!json_exceeds_depth(nested(3).as_bytes(), 3));
+        assert!(json_exceeds_depth(nested(4).as_bytes(), 3));
+        assert!(json_exceeds_depth(br#"{"a": {"b": [[1]]}}"#, 3));
+        assert!(!json_exceeds_depth(br#"{"a": "[[[[{{{{"}"#, 1));
+        assert!(!json_exceeds_depth(br#"["\"[[[[", "\\", "]"]"#, 1));
+        assert_eq!(value_depth(&json!({"a": [1, {"b": []}]})), 4);
+        assert_eq!(value_depth(&json!("scalar")), 0);
+    }
+
+    #[rocket::async_test]
+    async fn over_large_and_over_deep_bodies_are_rejected() {
+        let client = client(ByteUnit::Byte(256)).await;
+
+        let response = client.post("/echo").body(r#"{"a": [1, 2]}"#).dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.into_json::<JsonValue>().await, Some(json!({"a": [1, 2]})));
+
+        let too_large = format!("\"{}\"", "x".repeat(300));
+        assert_eq!(client.post("/echo").body(too_large).dispatch().await.status(), Status::PayloadTooLarge);
+
+        if settings::var("JSON_MAX_DEPTH").is_err() {
+            let too_deep = nested(DEFAULT_JSON_MAX_DEPTH + 1);
+            assert_eq!(client.post("/echo").body(too_deep).dispatch().await.status(), Status::BadRequest);
+            let deepest = nested(DEFAULT_JSON_MAX_DEPTH);
+            assert_eq!(client.post("/echo").body(deepest).dispatch().await.status(), Status::Ok);
+
+            let mut too_deep = json!(1);
+            for _ in 0..=DEFAULT_JSON_MAX_DEPTH {
+                too_deep = json!([too_deep]);
+            }
+            let response = client.post("/echo").header(msgpack_content_type()).body(to_msgpack(&too_deep).unwrap()).dispatch().await;
+            assert_eq!(response.status(), Status::BadRequest);
+        }
+    }
+
+    #[rocket::async_test]
+    async fn nested_values_round_trip_through_msgpack() {
+        let client = client(Limits::JSON).await;
+        let value = json!({"name": "router", "ports": [80, 443], "tls": {"enabled": true, "ratio": 0.5, "ca": null}, "tags": []});
+
+        let encoded = to_msgpack(&value).unwrap();
+        assert_eq!(rmp_serde::from_slice::<JsonValue>(&encoded).unwrap(), value);
+
+        let response = client.post("/echo").header(msgpack_content_type()).body(encoded).dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.into_json::<JsonValue>().await, Some(value));
+    }
+
+    #[rocket::async_test]
+    async fn gzip_bodies_are_limited_by_their_decompressed_size() {
+        let client = client(ByteUnit::Kibibyte(1)).await;
+        let gzip_encoding = || Header::new("Content-Encoding", "gzip");
+
+        let body = gzip(br#"{"a": [1, 2]}"#).await;
+        let response = client.post("/echo").header(gzip_encoding()).body(body).dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.into_json::<JsonValue>().await, Some(json!({"a": [1, 2]})));
+
+        // Half a megabyte of zeros compresses below the limit, but is rejected once decompressed
+        let bomb = gzip(format!("\"{}\"", "0".repeat(1 << 19)).as_bytes()).await;
+        assert!(bomb.len() < 1024);
+        let response = client.post("/echo").header(gzip_encoding()).body(bomb).dispatch().await;
+        assert_eq!(response.status(), Status::PayloadTooLarge);
+
+        let response = client.post("/echo").header(gzip_encoding()).body("not gzip").dispatch().await;
+        assert_eq!(response.status(), Status::BadRequest);
+    }
+}
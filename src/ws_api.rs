@@ -0,0 +1,190 @@
+// ws_api.rs
+use rocket::futures::{SinkExt, StreamExt};
+use rocket::serde::json::Value as JsonValue;
+use rocket::tokio::sync::broadcast::error::RecvError;
+use rocket::{get, routes, Route};
+use rocket_ws::{Channel, Message, WebSocket};
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::redis_registry::AsyncRegistry;
+use crate::redis_registry_api::{path_to_parts, validate_parts, OwnerRegistry};
+use crate::auth::ApiKey;
+
+// =======================================================
+// WebSocket Messages
+// =======================================================
+
+/// Operation sent by the client as a JSON text message
+#[derive(Debug, Deserialize)]
+struct WsRequest {
+    /// Client-chosen identifier echoed in the response
+    #[serde(default)]
+    id: JsonValue,
+    op: String,
+    #[serde(default)]
+    path: Option<String>,
+    #[serde(default)]
+    value: Option<JsonValue>,
+}
+
+/// Execute a single client operation and build its response message
+async fn handle_request(registry: &AsyncRegistry, api_key: &ApiKey, subscriptions: &mut Vec<Vec<String>>, text: &str) -> JsonValue {
+    let request: WsRequest = match serde_json::from_str(text) {
+        Ok(request) => request,
+        Err(e) => {
+            warn!("Invalid WebSocket message: {}", e);
+            return json!({ "id": JsonValue::Null, "ok": false, "error": format!("Invalid message: {}", e) });
+        }
+    };
+
+    debug!("WebSocket {} request received for path: {:?} from {}", request.op, request.path, api_key.identity());
+    let parts = path_to_parts(&request.path);
+    if let Err(rejected) = validate_parts(&parts) {
+        return json!({ "id": request.id, "ok": false, "error": rejected.1.0.error });
+    }
+
+    // Scoped tokens are checked per message, since the upgrade request carries no operation or path
+    if request.op != "unsubscribe" && !api_key.permits(&request.op, &parts) {
+        warn!("Scoped token {} is not allowed to {} path: {:?}", api_key.identity(), request.op, request.path);
+        return json!({ "id": request.id, "ok": false, "error": "Operation not allowed for this token" });
+    }
+
+    let result: Result<JsonValue, String> = match request.op.as_str() {
+        "get" => match registry.get_with_cache_status(&parts).await {
+            Ok((Some(value), _)) => Ok(value),
+            Ok((None, _)) => Err("Key not found".to_string()),
+            Err(e) => Err(e.to_string()),
+        },
+        "set" => match request.value {
+            Some(value) => registry.set(&parts, value).await.map(|_| JsonValue::from("OK")).map_err(|e| e.to_string()),
+            None => Err("Missing value".to_string()),
+        },
+        "delete" => match registry.delete(&parts).await {
+            Ok(true) => Ok(JsonValue::from("OK")),
+            Ok(false) => Err("Key not found".to_string()),
+            Err(e) => Err(e.to_string()),
+        },
+        "scan" => registry.scan(&parts).await.map(JsonValue::from).map_err(|e| e.to_string()),
+        "subscribe" => {
+            info!("WebSocket subscribed to prefix: {:?}", parts);
+            subscriptions.push(parts);
+            Ok(JsonValue::from("OK"))
+        },
+        "unsubscribe" => {
+            info!("WebSocket unsubscribed from prefix: {:?}", parts);
+            subscriptions.retain(|prefix| prefix != &parts);
+            Ok(JsonValue::from("OK"))
+        },
+        op => Err(format!("Unknown operation: {}", op)),
+    };
+
+    match result {
+        Ok(result) => json!({ "id": request.id, "ok": true, "result": result }),
+        Err(error) => {
+            debug!("WebSocket {} request failed: {}", request.op, error);
+            json!({ "id": request.id, "ok": false, "error": error })
+        },
+    }
+}
+
+/// Serve a WebSocket session: answer the client's operations and forward the change events of its subscriptions
+async fn run_session(registry: AsyncRegistry, api_key: ApiKey, mut stream: rocket_ws::stream::DuplexStream) -> rocket_ws::result::Result<()> {
+    let identity = api_key.identity();
+    info!("WebSocket session started for {}", identity);
+    let mut events = registry.subscribe();
+    let mut subscriptions: Vec<Vec<String>> = Vec::new();
+
+    loop {
+        let reply = rocket::tokio::select! {
+            message = stream.next() => match message {
+                Some(Ok(Message::Text(text))) => handle_request(&registry, &api_key, &mut subscriptions, &text).await,
+                Some(Ok(Message::Close(_))) | None => break,
+                Some(Ok(_)) => continue,
+                Some(Err(e)) => {
+                    warn!("WebSocket receive failed: {}", e);
+                    break;
+                },
+            },
+            event = events.recv() => match event {
+                Ok(event) if subscriptions.iter().any(|prefix| event.affects(prefix)) => {
+                    json!({ "event": event })
+                },
+                Ok(_) => continue,
+                Err(RecvError::Lagged(missed)) => {
+                    warn!("WebSocket subscriber missed {} change events", missed);
+                    json!({ "event": JsonValue::Null, "missed": missed })
+                },
+                Err(RecvError::Closed) => break,
+            },
+        };
+
+        if let Err(e) = stream.send(Message::Text(reply.to_string())).await {
+            warn!("WebSocket send failed: {}", e);
+            break;
+        }
+    }
+
+    info!("WebSocket session closed for {}", identity);
+    Ok(())
+}
+
+// =======================================================
+// Route Definitions
+// =======================================================
+
+/// Open a WebSocket session for registry operations and change events
+#[get("/ws")]
+pub fn ws_handler(api_key: ApiKey, registry: OwnerRegistry, ws: WebSocket) -> Channel<'static> {
+    debug!("WebSocket upgrade request received from {}", api_key.identity());
+    let registry = registry.0;
+    ws.channel(move |stream| Box::pin(run_session(registry, api_key, stream)))
+}
+
+// Function to get WebSocket routes
+pub fn routes() -> Vec<Route> {
+    routes![
+        ws_handler
+    ]
+}
+
+// Function to mount WebSocket routes
+pub fn mount_ws_routes(rocket: rocket::Rocket<rocket::Build>) -> rocket::Rocket<rocket::Build> {
+    rocket.mount("/registry", routes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::redis_registry::{RegistryConfig, StorageBackend};
+
+    fn registry() -> AsyncRegistry {
+        AsyncRegistry::new(&RegistryConfig {
+            owner_type: "test".to_string(),
+            owner_id: format!("{:016x}", rand::random::<u64>()),
+            storage: StorageBackend::String,
+            cache: None,
+            stale_cache: None,
+            cache_pubsub: false,
+        }).expect("REDIS_URL must point to a Redis server")
+    }
+
+    #[rocket::async_test]
+    #[ignore = "requires a Redis server at REDIS_URL"]
+    async fn get_answers_with_the_value_and_the_request_id() {
+        let registry = registry();
+        let api_key = ApiKey("disabled".to_string(), None);
+        let mut subscriptions = Vec::new();
+        registry.set(&path_to_parts(&Some("a/b".to_string())), json!({"x": 1})).await.unwrap();
+
+        let reply = handle_request(&registry, &api_key, &mut subscriptions, r#"{"id": 7, "op": "get", "path": "a/b"}"#).await;
+        assert_eq!(reply, json!({"id": 7, "ok": true, "result": {"x": 1}}));
+
+        let reply = handle_request(&registry, &api_key, &mut subscriptions, r#"{"id": "m", "op": "get", "path": "a/missing"}"#).await;
+        assert_eq!(reply, json!({"id": "m", "ok": false, "error": "Key not found"}));
+
+        let reply = handle_request(&registry, &api_key, &mut subscriptions, "not json").await;
+        assert_eq!(reply["ok"], json!(false));
+        assert_eq!(reply["id"], JsonValue::Null);
+    }
+}